@@ -0,0 +1,48 @@
+//! Compares cloning an error against copying it with `Error::clone_shallow`.
+//!
+//! These only differ with `repr_full`, and copying is only shallow with `shared_clone`.
+
+#[macro_use]
+extern crate criterion;
+
+use criterion::Criterion;
+use errcode::{Error, ErrorCode, error_info};
+use std::hint::black_box;
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BenchCode {
+    A,
+}
+
+/// Creates an error with several frames, so a deep copy has data to copy.
+fn deep_error() -> Error {
+    let mut error = Error::from_info(error_info!("disk full"));
+    for _ in 0..8 {
+        error = error.with_context(error_info!(BenchCode::A, "while saving"));
+    }
+    error
+}
+
+fn clone(c: &mut Criterion) {
+    c.bench_function("clone", |b| {
+        let error = deep_error();
+        b.iter(|| black_box(black_box(&error).clone()))
+    });
+    c.bench_function("clone_shallow", |b| {
+        let error = deep_error();
+        b.iter(|| black_box(black_box(&error).clone_shallow()))
+    });
+    c.bench_function("clone_then_context", |b| {
+        let error = deep_error();
+        b.iter(|| {
+            black_box(
+                black_box(&error)
+                    .clone()
+                    .with_context(error_info!("retrying")),
+            )
+        })
+    });
+}
+
+criterion_group!(benches, clone);
+criterion_main!(benches);
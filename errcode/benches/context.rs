@@ -0,0 +1,38 @@
+//! Measures adding context past the second context, where the unboxed representations decide
+//! whether to replace the stored context based on whether it has an error code.
+
+#[macro_use]
+extern crate criterion;
+
+use criterion::Criterion;
+use errcode::{Error, ErrorCode, error_info};
+use std::hint::black_box;
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BenchCode {
+    A,
+}
+
+fn context(c: &mut Criterion) {
+    c.bench_function("with_context_messages", |b| {
+        b.iter(|| {
+            let mut error = Error::from_info(error_info!("disk full"));
+            for _ in 0..16 {
+                error = error.with_context(black_box(error_info!("while saving")));
+            }
+            black_box(error)
+        })
+    });
+    c.bench_function("with_context_codes", |b| {
+        b.iter(|| {
+            let mut error = Error::from_info(error_info!("disk full"));
+            for _ in 0..16 {
+                error = error.with_context(black_box(error_info!(BenchCode::A, "while saving")));
+            }
+            black_box(error)
+        })
+    });
+}
+
+criterion_group!(benches, context);
+criterion_main!(benches);
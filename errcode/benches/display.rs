@@ -0,0 +1,38 @@
+//! Compares formatting errors with a single frame against errors with several frames.
+
+#[macro_use]
+extern crate criterion;
+
+use core::fmt::Write;
+use criterion::Criterion;
+use errcode::{Error, ErrorCode, error_info};
+use std::hint::black_box;
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BenchCode {
+    A,
+}
+
+/// Formats an error into a reused buffer, so only the formatting itself is measured.
+fn format_into(buffer: &mut String, error: &Error) {
+    buffer.clear();
+    write!(buffer, "{error}").unwrap();
+    black_box(&buffer);
+}
+
+fn display(c: &mut Criterion) {
+    c.bench_function("display_single_frame", |b| {
+        let error = Error::from_info(error_info!("disk full"));
+        let mut buffer = String::with_capacity(256);
+        b.iter(|| format_into(&mut buffer, black_box(&error)))
+    });
+    c.bench_function("display_multi_frame", |b| {
+        let error = Error::from_info(error_info!("disk full"))
+            .with_context(error_info!(BenchCode::A, "while saving"));
+        let mut buffer = String::with_capacity(256);
+        b.iter(|| format_into(&mut buffer, black_box(&error)))
+    });
+}
+
+criterion_group!(benches, display);
+criterion_main!(benches);
@@ -0,0 +1,72 @@
+//! Integration with the `miette` crate.
+
+use crate::{Error, Severity};
+use alloc::boxed::Box;
+use alloc::format;
+use core::fmt::{Debug, Display, Formatter};
+
+/// An [`Error`] wrapped for rendering with `miette`, created by [`Error::into_diagnostic`].
+///
+/// [`Error`] cannot implement [`std::error::Error`] itself, as this would conflict with its
+/// conversion from other error types. This wrapper implements both it and
+/// [`miette::Diagnostic`] instead.
+///
+/// The diagnostic has the following parts:
+/// * `code`: The current error code as `Type::Variant`, if any.
+/// * `severity`: The severity of the current error code, if any. [`Severity::Info`] maps to
+///   [`miette::Severity::Advice`], and [`Severity::Fatal`] to [`miette::Severity::Error`].
+/// * `url`: The documentation URL of the current error code, if any.
+///
+/// The message is the error formatted with [`Display`], which includes the locations of its
+/// frames. No source spans are attached, as errors do not store the source code they refer to.
+pub struct ErrorDiagnostic(Error);
+impl ErrorDiagnostic {
+    /// Returns the wrapped error.
+    pub fn into_inner(self) -> Error {
+        self.0
+    }
+}
+impl Display for ErrorDiagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+impl Debug for ErrorDiagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+impl std::error::Error for ErrorDiagnostic {}
+impl miette::Diagnostic for ErrorDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        let code = self.0.code()?;
+        Some(Box::new(format!("{}::{}", code.type_name, code.variant_name)))
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(match self.0.severity()? {
+            Severity::Info => miette::Severity::Advice,
+            Severity::Warning => miette::Severity::Warning,
+            Severity::Error | Severity::Fatal => miette::Severity::Error,
+        })
+    }
+
+    fn url<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.0.doc_url()?))
+    }
+}
+
+impl Error {
+    /// Wraps this error for rendering with `miette`.
+    ///
+    /// See [`ErrorDiagnostic`] for how the error is mapped to a diagnostic.
+    pub fn into_diagnostic(self) -> ErrorDiagnostic {
+        ErrorDiagnostic(self)
+    }
+}
+
+impl From<Error> for miette::Report {
+    fn from(error: Error) -> Self {
+        miette::Report::new(error.into_diagnostic())
+    }
+}
@@ -1,15 +1,61 @@
 //! Contains the raw implementation of the error code API.
 
+use crate::Error;
 use crate::error_impl::ErrorInfoImpl;
+use alloc::string::{String, ToString};
 use core::any::TypeId;
-use core::fmt::{Debug, Formatter};
+use core::fmt::{Debug, Display, Formatter, Write};
+
+/// How severe an error code is, set with the `#[code(severity = "...")]` attribute.
+///
+/// Severities are ordered from least to most severe.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// An expected condition that is only worth noting, set with `"info"`.
+    Info,
+    /// A condition that may need attention, set with `"warning"`.
+    Warning,
+    /// A failure of the current operation, set with `"error"`.
+    Error,
+    /// A failure the program cannot recover from, set with `"fatal"`.
+    Fatal,
+}
+
+/// The reason the error code of an [`Error`](crate::Error) could not be converted to a given type,
+/// returned by [`Error::as_code_enum`](crate::Error::as_code_enum).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CodeError {
+    /// The error has no error code.
+    NoCode,
+    /// The error has an error code of a different type.
+    WrongType {
+        /// The name of the type that was requested.
+        expected: &'static str,
+        /// The name of the type of the error code.
+        actual: &'static str,
+    },
+}
+impl Display for CodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CodeError::NoCode => f.write_str("error has no error code"),
+            CodeError::WrongType { expected, actual } => {
+                write!(f, "expected an error code of type `{expected}`, found `{actual}`")
+            }
+        }
+    }
+}
+impl core::error::Error for CodeError {}
 
 /// Represents the info underlying an error code.
 pub struct ErrorCodeInfo {
     /// The type ID of this error code.
     pub tid: TypeId,
 
-    /// The value of this error code.
+    /// The value of this error code, truncated to 32 bits.
+    ///
+    /// Negative values are stored in two's complement. Use [`ErrorCodeInfo::value_u64`] or
+    /// [`ErrorCodeInfo::value_i64`] for codes that do not fit in 32 bits.
     pub value: u32,
 
     /// The name of the type underlying this error code.
@@ -20,31 +66,245 @@ pub struct ErrorCodeInfo {
 
     /// The message this error code should be translated to.
     pub message: Option<&'static str>,
+
+    /// A note explaining why this error code is deprecated, if it is.
+    pub deprecated: Option<&'static str>,
+
+    /// A template for the URL documenting this error code, if there is one.
+    ///
+    /// See [`ErrorCodeInfo::doc_url`] for the supported substitutions.
+    pub doc_url: Option<&'static str>,
+
+    /// The logical group this error code belongs to, such as the subsystem it comes from.
+    pub group: Option<&'static str>,
+
+    /// How severe this error code is, if it has been set.
+    pub severity: Option<Severity>,
+
+    /// Arbitrary `(key, value)` annotations on this error code.
+    ///
+    /// See [`ErrorCodeInfo::meta`] for how these are set.
+    pub metadata: &'static [(&'static str, &'static str)],
+
+    /// The full value of this error code, in two's complement if it is negative.
+    pub(crate) full_value: u64,
+
+    /// Whether `full_value` represents a negative number.
+    pub(crate) negative: bool,
+
+    /// Whether this is the code of a `#[code(transparent)]` payload, which is distinct from a
+    /// unit variant with the same value.
+    pub(crate) payload: bool,
 }
 impl ErrorCodeInfo {
+    /// Creates the info for an error code with a given type ID, names and value.
+    ///
+    /// The other fields are empty, and can be set afterwards, including in a `static`
+    /// initializer. Panics if the value is neither an `i64` nor a `u64`.
+    pub const fn new(
+        tid: TypeId,
+        type_name: &'static str,
+        variant_name: &'static str,
+        value: i128,
+    ) -> Self {
+        assert!(value >= i64::MIN as i128 && value <= u64::MAX as i128);
+        ErrorCodeInfo {
+            tid,
+            value: value as u32,
+            type_name,
+            variant_name,
+            message: None,
+            deprecated: None,
+            doc_url: None,
+            group: None,
+            severity: None,
+            metadata: &[],
+            full_value: value as u64,
+            negative: value < 0,
+            payload: false,
+        }
+    }
+
+    /// Returns the value of this error code, truncated to 32 bits.
+    ///
+    /// This is the same as the [`value`](ErrorCodeInfo#structfield.value) field.
+    pub const fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// Returns the full value of this error code.
+    ///
+    /// Negative values are returned in two's complement.
+    pub const fn value_u64(&self) -> u64 {
+        self.full_value
+    }
+
+    /// Returns the value of this error code as a signed number, or `None` if it is an unsigned
+    /// value above [`i64::MAX`].
+    pub const fn value_i64(&self) -> Option<i64> {
+        if self.negative || self.full_value <= i64::MAX as u64 {
+            Some(self.full_value as i64)
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the value of this error code is negative.
+    pub const fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Returns the deprecation note for this error code, if it is deprecated.
+    ///
+    /// This is set with the `#[code(deprecated = "...")]` attribute. That attribute does not make
+    /// the variant itself deprecated, as a derive macro cannot add attributes to the enum it is
+    /// applied to, and it generates no accessor for each variant. To have the compiler warn about
+    /// uses of the variant, also mark it `#[deprecated]`.
+    pub const fn deprecation(&self) -> Option<&'static str> {
+        self.deprecated
+    }
+
+    /// Returns the URL documenting this error code, if there is one.
+    ///
+    /// This is set with the `#[code(doc_url = "...")]` attribute, on either the enum or a variant.
+    /// `{value}` in the URL is replaced with the value of the error code, and `{variant}` with the
+    /// name of the variant.
+    pub fn doc_url(&self) -> Option<String> {
+        let template = self.doc_url?;
+        let value = DisplayValue(self).to_string();
+        Some(
+            template
+                .replace("{value}", &value)
+                .replace("{variant}", self.variant_name),
+        )
+    }
+
+    /// Returns the metadata value for the given key, if this error code has one.
+    ///
+    /// This is set with the `#[code(meta(key = "value", ...))]` attribute, on either the enum or
+    /// a variant. Entries on a variant replace entries with the same key on the enum.
+    pub fn meta(&self, key: &str) -> Option<&'static str> {
+        self.metadata
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+    }
+
+    /// Writes an explanation of this error code, such as for a `--explain` command.
+    ///
+    /// The first line is the code and its value, followed by one `key: value` line for each of
+    /// the message, severity, group, deprecation note, documentation URL and metadata entries
+    /// that are set.
+    pub fn explain<W: Write + ?Sized>(&self, w: &mut W) -> core::fmt::Result {
+        write!(w, "{}::{} (value {})", self.type_name, self.variant_name, DisplayValue(self))?;
+        if let Some(message) = self.message {
+            write!(w, "\nmessage: {message}")?;
+        }
+        if let Some(severity) = self.severity {
+            let severity = match severity {
+                Severity::Info => "info",
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+                Severity::Fatal => "fatal",
+            };
+            write!(w, "\nseverity: {severity}")?;
+        }
+        if let Some(group) = self.group {
+            write!(w, "\ngroup: {group}")?;
+        }
+        if let Some(deprecated) = self.deprecated {
+            write!(w, "\ndeprecated: {deprecated}")?;
+        }
+        if let Some(url) = self.doc_url() {
+            write!(w, "\ndocs: {url}")?;
+        }
+        for (key, value) in self.metadata {
+            write!(w, "\n{key}: {value}")?;
+        }
+        Ok(())
+    }
+
     pub fn is_value<T: ErrorCodePrivate>(&self, val: T) -> bool {
-        self.tid == TypeId::of::<T>() && val.is_value(self.value)
+        self.tid == TypeId::of::<T>()
+            && match self.payload {
+                true => val.is_payload(self.full_value),
+                false => val.is_value(self.full_value),
+            }
     }
 
     pub fn decode_value<T: ErrorCodePrivate>(&self) -> Option<T> {
         if self.tid == TypeId::of::<T>() {
-            Some(T::from_value(self.value))
+            Some(match self.payload {
+                true => T::from_payload(self.full_value),
+                false => T::from_value(self.full_value),
+            })
         } else {
             None
         }
     }
+
+    /// Returns whether two infos are for the same error code.
+    pub(crate) fn same_code(&self, other: &ErrorCodeInfo) -> bool {
+        self.tid == other.tid
+            && self.full_value == other.full_value
+            && self.payload == other.payload
+    }
 }
 impl Debug for ErrorCodeInfo {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("ErrorCodeInfo")
-            .field("variant", &format_args!("{}::{}", self.type_name, self.value))
+            .field("variant", &format_args!("{}::{}", self.type_name, self.variant_name))
+            .field("value", &format_args!("{}", DisplayValue(self)))
             .field("message", &self.message)
-            .finish()
+            .field("deprecated", &self.deprecated)
+            .field("doc_url", &self.doc_url)
+            .field("group", &self.group)
+            .field("severity", &self.severity)
+            .field("metadata", &self.metadata)
+            .finish_non_exhaustive()
+    }
+}
+impl Display for ErrorCodeInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}::{}", self.type_name, self.variant_name)?;
+        if let Some(message) = self.message {
+            write!(f, " ({message})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Helper for displaying the value of an error code with the correct sign.
+struct DisplayValue<'a>(&'a ErrorCodeInfo);
+impl Display for DisplayValue<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        if self.0.negative {
+            Display::fmt(&(self.0.full_value as i64), f)
+        } else {
+            Display::fmt(&self.0.value_u64(), f)
+        }
     }
 }
 
 /// A type that can be used as an error code for this crate.
-pub trait ErrorCode: 'static + ErrorCodePrivate {}
+pub trait ErrorCode: 'static + ErrorCodePrivate {
+    /// Returns whether an error has this error code, the same as [`Error::is`].
+    fn matches(self, err: &Error) -> bool
+    where
+        Self: Sized,
+    {
+        err.is(self)
+    }
+
+    /// Returns whether any frame of an error has this error code, the same as
+    /// [`Error::caused_by_code`].
+    fn caused(self, err: &Error) -> bool
+    where
+        Self: Sized,
+    {
+        err.caused_by_code(self)
+    }
+}
 
 /// The internal error code trait implementation.
 pub trait ErrorCodePrivate: 'static {
@@ -60,10 +320,31 @@ pub trait ErrorCodePrivate: 'static {
     fn error_source(self) -> &'static ErrorInfoImpl;
 
     /// Returns true if the value matches this enum.
-    fn is_value(self, value: u32) -> bool;
+    #[allow(clippy::wrong_self_convention)]
+    fn is_value(self, value: u64) -> bool;
 
     /// Returns an enum value corresponding to this error code.
     ///
     /// This should *panic* if the value does not correspond to a known enum variant.
-    fn from_value(value: u32) -> Self;
+    fn from_value(value: u64) -> Self;
+
+    /// Returns true if the value is the payload of the `#[code(transparent)]` variant of this
+    /// enum.
+    #[allow(clippy::wrong_self_convention)]
+    fn is_payload(self, _value: u64) -> bool
+    where
+        Self: Sized,
+    {
+        false
+    }
+
+    /// Returns the `#[code(transparent)]` variant with a given payload.
+    ///
+    /// This should *panic* if the value does not fit in the payload.
+    fn from_payload(value: u64) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_value(value)
+    }
 }
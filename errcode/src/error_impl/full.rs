@@ -3,56 +3,225 @@
 //! TODO: Document
 
 use super::*;
-use alloc::borrow::Cow;
 use alloc::boxed::Box;
-use alloc::string::ToString;
-use alloc::vec;
+use alloc::string::String;
 use alloc::vec::Vec;
 
+/// The maximum number of steps [`ErrorImpl::push_context`] grows an error to.
+///
+/// Past this, each new context replaces the outermost step instead, and the replaced steps are
+/// marked as omitted. This bounds the memory used by errors that have context added in a loop.
+#[cfg(not(feature = "heapless"))]
+const MAX_CONTEXT_DEPTH: usize = 1024;
+
+/// The maximum number of steps an error may have, which is the capacity of [`Steps`].
+#[cfg(feature = "heapless")]
+const MAX_CONTEXT_DEPTH: usize = HEAPLESS_STEP_CAPACITY;
+
+/// The list of steps of an error, from the innermost step to the outermost.
+#[cfg(not(feature = "heapless"))]
+type Steps = Vec<ErrorSourceStep>;
+
+/// The list of steps of an error, from the innermost step to the outermost.
+#[cfg(feature = "heapless")]
+type Steps = heapless::Vec<ErrorSourceStep, MAX_CONTEXT_DEPTH>;
+
+/// The pointer to the data of an error.
+///
+/// With `shared_clone`, the data is shared between clones, and copied when a shared error is
+/// modified, so cloning an error is cheap. Otherwise, cloning an error copies its data.
+#[cfg(feature = "shared_clone")]
+type InnerPtr = Arc<ErrorImplInner>;
+
+/// The pointer to the data of an error.
+///
+/// With `shared_clone`, the data is shared between clones, and copied when a shared error is
+/// modified, so cloning an error is cheap. Otherwise, cloning an error copies its data.
+#[cfg(not(feature = "shared_clone"))]
+type InnerPtr = Box<ErrorImplInner>;
+
 #[repr(transparent)]
 #[derive(Clone)]
 pub struct ErrorImpl {
-    inner: Box<ErrorImplInner>,
+    inner: InnerPtr,
 }
 #[derive(Clone)]
 struct ErrorImplInner {
-    steps: Vec<ErrorSourceStep>,
+    steps: Steps,
     current_code: Option<&'static ErrorCodeInfo>,
+    /// Whether `current_code` has been pinned by [`ErrorImplFunctions::freeze_code`].
+    code_frozen: bool,
+    /// The number of leading steps whose codes were removed by [`ErrorImplFunctions::take_code`],
+    /// and no longer count towards `current_code`.
+    code_floor: usize,
+    attachments: Vec<Attachment>,
+    notes: Vec<&'static str>,
+}
+impl ErrorImplInner {
+    /// Returns the step the error was created with, skipping steps for its source chain.
+    ///
+    /// This is only `None` for an error truncated to no steps.
+    fn origin_step(&self) -> Option<&ErrorSourceStep> {
+        self.steps.iter().find(|x| !x.from_source)
+    }
+
+    fn origin_step_mut(&mut self) -> Option<&mut ErrorSourceStep> {
+        self.steps.iter_mut().find(|x| !x.from_source)
+    }
+
+    /// Recomputes `current_code` from the steps above `code_floor`, unless it is frozen.
+    fn update_current_code(&mut self) {
+        if !self.code_frozen {
+            self.current_code = self.steps[self.code_floor..]
+                .iter()
+                .rev()
+                .find_map(ErrorSourceStep::code);
+        }
+    }
+}
+impl ErrorImpl {
+    /// Returns the data of this error for modification, copying it first if it is shared.
+    #[inline(always)]
+    fn inner_mut(&mut self) -> &mut ErrorImplInner {
+        #[cfg(feature = "shared_clone")]
+        return Arc::make_mut(&mut self.inner);
+        #[cfg(not(feature = "shared_clone"))]
+        return &mut self.inner;
+    }
+
+    /// Returns the data of this error, copying it if it is shared.
+    #[inline(always)]
+    fn into_inner(self) -> ErrorImplInner {
+        #[cfg(feature = "shared_clone")]
+        return Arc::unwrap_or_clone(self.inner);
+        #[cfg(not(feature = "shared_clone"))]
+        return *self.inner;
+    }
 }
 impl ErrorImplFunctions for ErrorImpl {
     type FrameIter<'a> = ErrorImplIter<'a>;
 
     #[track_caller]
     #[inline(never)]
-    fn new(source: ErrorOrigin, args: Option<&Arguments<'_>>) -> ErrorImpl {
-        ErrorImpl {
-            inner: Box::new(ErrorImplInner {
-                steps: vec![ErrorSourceStep {
-                    static_info: source,
-                    formatted_message: format_args(args),
-                    location: Location::caller(),
-                }],
+    fn new(source: ErrorOrigin, args: Option<&Arguments<'_>>) -> Self {
+        let mut steps = Steps::new();
+        push_step(
+            &mut steps,
+            ErrorSourceStep {
+                static_info: source,
+                formatted_message: format_args(args),
+                location: Some(Location::caller()),
+                rewritten_locations: None,
+                from_source: false,
+                frames_omitted: false,
+                cause_boundary: false,
+            },
+        );
+        let error = ErrorImpl {
+            inner: InnerPtr::new(ErrorImplInner {
+                steps,
                 current_code: match source {
                     ErrorOrigin::StaticOrigin(o) => o.error_code,
                     ErrorOrigin::TypeOrigin(_, Some(code)) => code.error_code,
                     _ => None,
                 },
+                code_frozen: false,
+                code_floor: 0,
+                attachments: Vec::new(),
+                notes: Vec::new(),
             }),
-        }
+        };
+        #[cfg(fuzzing)]
+        error.assert_invariants();
+        error
     }
 
     #[track_caller]
     #[inline(never)]
-    fn push_context(&mut self, source: &'static ErrorInfoImpl, args: Option<&Arguments<'_>>) {
-        let step = ErrorSourceStep {
+    fn push_context(
+        &mut self,
+        source: &'static ErrorInfoImpl,
+        args: Option<&Arguments<'_>>,
+    ) -> bool {
+        let inner = self.inner_mut();
+        let mut step = ErrorSourceStep {
             static_info: ErrorOrigin::StaticOrigin(source),
             formatted_message: format_args(args),
-            location: Location::caller(),
+            location: Some(Location::caller()),
+            rewritten_locations: None,
+            from_source: false,
+            frames_omitted: false,
+            cause_boundary: false,
         };
-        self.inner.steps.push(step);
-        if source.error_code.is_some() {
-            self.inner.current_code = source.error_code;
+        if inner.steps.len() < MAX_CONTEXT_DEPTH {
+            push_step(&mut inner.steps, step);
+            if source.error_code.is_some() && !inner.code_frozen {
+                inner.current_code = source.error_code;
+            }
+        } else {
+            let last = inner.steps.last_mut().unwrap();
+            step.frames_omitted = true;
+            step.cause_boundary = last.cause_boundary;
+            *last = step;
+            inner.code_floor = inner.code_floor.min(inner.steps.len() - 1);
+            inner.update_current_code();
         }
+        #[cfg(fuzzing)]
+        self.assert_invariants();
+        true
+    }
+
+    #[track_caller]
+    fn push_marker(&mut self) {
+        /// The info of marker steps, which only have the location they were pushed at.
+        static MARKER_INFO: ErrorInfoImpl = ErrorInfoImpl::new(None, StaticMessageInfo::None, None);
+        self.push_context(&MARKER_INFO, None);
+    }
+
+    #[track_caller]
+    fn push_sources(&mut self, error: &dyn core::error::Error) {
+        /// The info of steps created for the source chain, which only have a formatted message.
+        static SOURCE_INFO: ErrorInfoImpl = ErrorInfoImpl::new(None, StaticMessageInfo::None, None);
+
+        let inner = self.inner_mut();
+        let sources =
+            core::iter::successors(error.source(), |x| x.source()).map(|source| ErrorSourceStep {
+                static_info: ErrorOrigin::StaticOrigin(&SOURCE_INFO),
+                formatted_message: Some(MessageContainer::formatted(format_args!("{source}"))),
+                location: Some(Location::caller()),
+                rewritten_locations: None,
+                from_source: true,
+                frames_omitted: false,
+                cause_boundary: false,
+            });
+
+        #[cfg(not(feature = "heapless"))]
+        {
+            let mut steps: Vec<_> = sources.take(MAX_CONTEXT_DEPTH).collect();
+            steps.reverse();
+            inner.code_floor += steps.len();
+            steps.append(&mut inner.steps);
+            inner.steps = steps;
+        }
+
+        // only the sources closest to the origin that fit are kept, beneath a note that the others
+        // were omitted
+        #[cfg(feature = "heapless")]
+        {
+            let room = MAX_CONTEXT_DEPTH - inner.steps.len();
+            let mut count = 0;
+            for (i, step) in sources.enumerate() {
+                if i == room {
+                    inner.steps[0].frames_omitted = true;
+                    break;
+                }
+                push_step_front(&mut inner.steps, step);
+                count += 1;
+            }
+            inner.code_floor += count;
+        }
+        #[cfg(fuzzing)]
+        self.assert_invariants();
     }
 
     #[inline(always)]
@@ -60,103 +229,436 @@ impl ErrorImplFunctions for ErrorImpl {
         self.inner.current_code
     }
 
+    fn root_code(&self) -> Option<&'static ErrorCodeInfo> {
+        self.inner.steps.iter().find_map(ErrorSourceStep::code)
+    }
+
+    fn is_conversion(&self) -> bool {
+        matches!(
+            self.inner.origin_step().map(|x| x.static_info),
+            Some(ErrorOrigin::TypeOrigin(..))
+        )
+    }
+
+    fn origin_type_name(&self) -> Option<&'static str> {
+        match self.inner.origin_step()?.static_info {
+            ErrorOrigin::TypeOrigin(name, _) => Some(name),
+            ErrorOrigin::StaticOrigin(_) => None,
+        }
+    }
+
+    fn freeze_code(&mut self) {
+        self.inner_mut().code_frozen = true;
+    }
+
+    fn take_code(&mut self) -> Option<&'static ErrorCodeInfo> {
+        let inner = self.inner_mut();
+        inner.code_frozen = false;
+        inner.code_floor = inner.steps.len();
+        inner.current_code.take()
+    }
+
+    fn retype_origin(&mut self, name: &'static str) {
+        let inner = self.inner_mut();
+        if let Some(step) = inner.origin_step_mut()
+            && let ErrorOrigin::TypeOrigin(_, code) = step.static_info
+        {
+            step.static_info = ErrorOrigin::TypeOrigin(name, code);
+        }
+    }
+
+    fn graft_cause(&mut self, cause: Self) {
+        let inner = self.inner_mut();
+        let mut cause = cause.into_inner();
+        // only the innermost steps of the cause that fit are kept
+        #[cfg(feature = "heapless")]
+        let omitted = {
+            let room = MAX_CONTEXT_DEPTH - inner.steps.len();
+            let omitted = cause.steps.len() > room;
+            cause.steps.truncate(room);
+            cause.code_floor = cause.code_floor.min(room);
+            omitted
+        };
+        let boundary = cause.steps.len();
+        append_steps(&mut cause.steps, &mut inner.steps);
+        if let Some(step) = cause.steps.get_mut(boundary) {
+            step.cause_boundary = true;
+            #[cfg(feature = "heapless")]
+            {
+                step.frames_omitted |= omitted;
+            }
+        }
+        cause.attachments.append(&mut inner.attachments);
+        cause.notes.append(&mut inner.notes);
+
+        inner.steps = cause.steps;
+        inner.attachments = cause.attachments;
+        inner.notes = cause.notes;
+        if inner.code_floor != 0 {
+            // the code was taken from this error, so the code of the cause is not used either
+            inner.code_floor += boundary;
+        } else {
+            inner.code_floor = cause.code_floor;
+            if !inner.code_frozen {
+                inner.current_code = inner.current_code.or(cause.current_code);
+            }
+        }
+        #[cfg(feature = "heapless")]
+        if omitted {
+            inner.update_current_code();
+        }
+        #[cfg(fuzzing)]
+        self.assert_invariants();
+    }
+
+    fn map_messages<F: Fn(&str) -> String>(&mut self, f: F) {
+        let inner = self.inner_mut();
+        for step in &mut inner.steps {
+            if let Some(message) = step.context_frame().message() {
+                step.formatted_message =
+                    Some(MessageContainer::formatted(format_args!("{}", f(message))));
+            }
+        }
+    }
+
+    fn replace_message(&mut self, message: Cow<'static, str>) -> bool {
+        let inner = self.inner_mut();
+        let Some(step) = inner.steps.last_mut() else {
+            return false;
+        };
+        step.formatted_message = Some(match message {
+            Cow::Borrowed(message) => MessageContainer::Static(message),
+            Cow::Owned(message) => MessageContainer::formatted(format_args!("{message}")),
+        });
+        true
+    }
+
+    #[cfg(feature = "testing")]
+    fn clear_caller_locations(&mut self) {
+        let inner = self.inner_mut();
+        for step in &mut inner.steps {
+            step.location = None;
+            step.rewritten_locations = None;
+        }
+    }
+
+    fn rewrite_locations<F: Fn(DecodedLocation) -> DecodedLocation>(&mut self, f: F) {
+        let inner = self.inner_mut();
+        for step in &mut inner.steps {
+            let rewritten = RewrittenLocations {
+                location: step.location().map(&f),
+                info_location: step.info_location().map(&f),
+            };
+            step.rewritten_locations = Some(Box::new(rewritten));
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        let inner = self.inner_mut();
+        inner.steps.truncate(len);
+        inner.code_floor = inner.code_floor.min(inner.steps.len());
+        inner.update_current_code();
+        #[cfg(fuzzing)]
+        self.assert_invariants();
+    }
+
+    fn attach<D: Display + Send + Sync + 'static>(&mut self, attachment: D) {
+        let inner = self.inner_mut();
+        inner.attachments.push(Arc::new(attachment));
+    }
+
+    fn attachments(&self) -> &[Attachment] {
+        &self.inner.attachments
+    }
+
+    fn push_note(&mut self, note: &'static str) {
+        self.inner_mut().notes.push(note);
+    }
+
+    fn notes(&self) -> &[&'static str] {
+        &self.inner.notes
+    }
+
+    fn source_location(&self) -> Option<DecodedLocation> {
+        self.inner.steps.first()?.location()
+    }
+
+    fn origin_frame(&self) -> Option<ErrorFrameImpl> {
+        Some(self.inner.origin_step()?.context_frame())
+    }
+
     fn iter(&self) -> Self::FrameIter<'_> {
         ErrorImplIter {
             underlying: &self.inner,
-            idx: self.inner.steps.len(),
-            phase: FrameLoopPhase::Context,
+            front: 0,
+            back: self.inner.steps.len().max(1) * SLOTS_PER_STEP,
         }
     }
+
+    fn assert_invariants(&self) {
+        if self.inner.code_frozen {
+            return;
+        }
+
+        assert!(self.inner.code_floor <= self.inner.steps.len(), "code floor is out of bounds");
+        let expected_code = self.inner.steps[self.inner.code_floor..]
+            .iter()
+            .rev()
+            .find_map(ErrorSourceStep::code);
+        assert!(
+            match (self.inner.current_code, expected_code) {
+                (Some(a), Some(b)) => a.same_code(b),
+                (None, None) => true,
+                _ => false,
+            },
+            "current code does not match the steps"
+        );
+    }
 }
 
-fn format_args(args: Option<&Arguments>) -> Option<Cow<'static, str>> {
+fn format_args(args: Option<&Arguments>) -> Option<MessageContainer> {
     if let Some(args) = args {
         if let Some(str) = args.as_str() {
-            Some(Cow::Borrowed(str))
+            Some(MessageContainer::Static(str))
+        } else if let Some(str) = crate::message_store::store_message(args) {
+            Some(MessageContainer::Static(str))
         } else {
-            Some(args.to_string().into())
+            Some(MessageContainer::formatted(*args))
         }
     } else {
         None
     }
 }
 
+/// Pushes a step onto a list with room for it.
+fn push_step(steps: &mut Steps, step: ErrorSourceStep) {
+    #[cfg(not(feature = "heapless"))]
+    steps.push(step);
+    #[cfg(feature = "heapless")]
+    if steps.push(step).is_err() {
+        unreachable!("no room for a new step");
+    }
+}
+
+/// Inserts a step beneath the steps of a list with room for it.
+#[cfg(feature = "heapless")]
+fn push_step_front(steps: &mut Steps, step: ErrorSourceStep) {
+    if steps.insert(0, step).is_err() {
+        unreachable!("no room for a new step");
+    }
+}
+
+/// Moves all steps of `other` onto the end of `steps`, which must have room for them.
+fn append_steps(steps: &mut Steps, other: &mut Steps) {
+    #[cfg(not(feature = "heapless"))]
+    steps.append(other);
+    #[cfg(feature = "heapless")]
+    for step in core::mem::take(other) {
+        push_step(steps, step);
+    }
+}
+
 #[derive(Clone)]
 struct ErrorSourceStep {
     static_info: ErrorOrigin,
-    location: &'static Location<'static>,
-    formatted_message: Option<Cow<'static, str>>,
+    /// The location this step was created at, or `None` to use the location of `static_info`.
+    location: Option<&'static Location<'static>>,
+    /// The locations set by [`ErrorImplFunctions::rewrite_locations`], which replace `location`
+    /// and the location of `static_info` when they are shown.
+    rewritten_locations: Option<Box<RewrittenLocations>>,
+    formatted_message: Option<MessageContainer>,
+    /// Whether this step was created for the source chain of a converted error.
+    from_source: bool,
+    /// Whether steps between this one and the one before it were dropped to bound the size of the
+    /// error.
+    frames_omitted: bool,
+    /// Whether the steps before this one belong to an error attached as a cause.
+    cause_boundary: bool,
+}
+
+/// The locations of a step after [`ErrorImplFunctions::rewrite_locations`].
+#[derive(Clone)]
+struct RewrittenLocations {
+    location: Option<DecodedLocation>,
+    info_location: Option<DecodedLocation>,
 }
 
+impl ErrorSourceStep {
+    /// Returns the error code attached to this step, if any.
+    fn code(&self) -> Option<&'static ErrorCodeInfo> {
+        match self.static_info {
+            ErrorOrigin::StaticOrigin(o) => o.error_code,
+            ErrorOrigin::TypeOrigin(_, code) => code.and_then(|x| x.error_code),
+        }
+    }
+
+    /// Returns the static info of this step, if any.
+    fn info(&self) -> Option<&'static ErrorInfoImpl> {
+        match self.static_info {
+            ErrorOrigin::StaticOrigin(info) => Some(info),
+            ErrorOrigin::TypeOrigin(_, info) => info,
+        }
+    }
+
+    /// Returns the location this step was created at, falling back to the location of its
+    /// static info.
+    fn location(&self) -> Option<DecodedLocation> {
+        if let Some(rewritten) = &self.rewritten_locations {
+            return rewritten.location;
+        }
+        match self.location {
+            Some(location) => Some(location.into()),
+            None => self.info_location(),
+        }
+    }
+
+    /// Returns the location the static info of this step was constructed at.
+    fn info_location(&self) -> Option<DecodedLocation> {
+        match &self.rewritten_locations {
+            Some(rewritten) => rewritten.info_location,
+            None => self.info().and_then(|x| x.location.copied()),
+        }
+    }
+
+    /// Returns the frame containing the message and code of this step.
+    fn context_frame(&self) -> ErrorFrameImpl {
+        let info = self.info();
+        ErrorFrameImpl {
+            data: match &self.formatted_message {
+                None => match self.static_info {
+                    ErrorOrigin::StaticOrigin(origin) => {
+                        ErrorFrameData::decode_static(Some(origin), None)
+                    }
+                    ErrorOrigin::TypeOrigin(ty, origin) => {
+                        ErrorFrameData::TypeFrame(ty, origin.and_then(|x| x.error_code))
+                    }
+                },
+                Some(message) => match self.static_info {
+                    ErrorOrigin::TypeOrigin(ty, origin) => ErrorFrameData::ConvertedFrame(
+                        ty,
+                        message.clone(),
+                        origin.and_then(|x| x.error_code),
+                    ),
+                    ErrorOrigin::StaticOrigin(_) => {
+                        ErrorFrameData::decode_static(info, Some(message.clone()))
+                    }
+                },
+            },
+            location: self.location(),
+        }
+    }
+
+    /// Returns a frame noting where the [`ErrorInfoImpl`] of this step was constructed, if it is
+    /// far from where the step itself was created.
+    fn location_mismatch_frame(&self) -> Option<ErrorFrameImpl> {
+        let location = DecodedLocation::from(self.location?);
+        match self.info().and_then(|x| x.location) {
+            Some(origin) if !origin.is_near(location) => Some(ErrorFrameImpl {
+                data: ErrorFrameData::InternalContext(InternalContextType::ErrorTypeConstructed),
+                location: self.info_location(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns a frame noting that steps before this one were dropped, if any were.
+    fn frames_omitted_frame(&self) -> Option<ErrorFrameImpl> {
+        self.frames_omitted.then_some(ErrorFrameImpl {
+            data: ErrorFrameData::InternalContext(InternalContextType::FurtherFramesOmitted),
+            location: None,
+        })
+    }
+
+    /// Returns a frame separating this step from the steps of an attached cause, if needed.
+    fn cause_boundary_frame(&self) -> Option<ErrorFrameImpl> {
+        self.cause_boundary.then_some(ErrorFrameImpl {
+            data: ErrorFrameData::InternalContext(InternalContextType::CauseBoundary),
+            location: None,
+        })
+    }
+}
+
+/// The number of frame slots used by each step in [`ErrorImplIter`].
+const SLOTS_PER_STEP: usize = 4;
+
+/// Iterates the frames of an [`ErrorImpl`].
+///
+/// Each step has four frame slots: its context frame, followed by an optional location mismatch
+/// frame, an optional frames omitted frame and an optional cause boundary frame. Slots are
+/// numbered in iteration order, starting from the last step pushed.
 pub struct ErrorImplIter<'a> {
     underlying: &'a ErrorImplInner,
-    idx: usize,
-    phase: FrameLoopPhase,
+    front: usize,
+    back: usize,
 }
-#[derive(Copy, Clone, Eq, PartialEq)]
-enum FrameLoopPhase {
-    Context,
-    LocationMismatchFrame,
-    Ended,
+impl ErrorImplIter<'_> {
+    /// Returns the step a slot belongs to, or `None` if the error has no steps.
+    fn step_for_slot(&self, slot: usize) -> Option<&ErrorSourceStep> {
+        let steps = &self.underlying.steps;
+        steps.get(steps.len().checked_sub(1 + slot / SLOTS_PER_STEP)?)
+    }
+
+    fn frame_at(&self, slot: usize) -> Option<ErrorFrameImpl> {
+        let Some(step) = self.step_for_slot(slot) else {
+            // an error without steps still has one frame, so it never displays as empty
+            return (slot == 0).then_some(ErrorFrameImpl {
+                data: ErrorFrameData::NormalFrame(None, None),
+                location: None,
+            });
+        };
+        match slot % SLOTS_PER_STEP {
+            0 => Some(step.context_frame()),
+            1 => step.location_mismatch_frame(),
+            2 => step.frames_omitted_frame(),
+            _ => step.cause_boundary_frame(),
+        }
+    }
+
+    fn has_frame_at(&self, slot: usize) -> bool {
+        let Some(step) = self.step_for_slot(slot) else {
+            return slot == 0;
+        };
+        match slot % SLOTS_PER_STEP {
+            0 => true,
+            1 => step.location_mismatch_frame().is_some(),
+            2 => step.frames_omitted,
+            _ => step.cause_boundary,
+        }
+    }
 }
 impl Iterator for ErrorImplIter<'_> {
     type Item = ErrorFrameImpl;
     fn next(&mut self) -> Option<Self::Item> {
-        while self.idx > 0 {
-            let frame = &self.underlying.steps[self.idx - 1];
-
-            if self.phase == FrameLoopPhase::Context {
-                self.phase = FrameLoopPhase::LocationMismatchFrame;
-
-                let info = match frame.static_info {
-                    ErrorOrigin::StaticOrigin(info) => Some(info),
-                    ErrorOrigin::TypeOrigin(_, info) => info,
-                };
-                return Some(ErrorFrameImpl {
-                    data: match &frame.formatted_message {
-                        None => match frame.static_info {
-                            ErrorOrigin::StaticOrigin(origin) => {
-                                ErrorFrameData::decode_static(Some(origin), None)
-                            }
-                            ErrorOrigin::TypeOrigin(ty, origin) => {
-                                ErrorFrameData::TypeFrame(ty, origin.and_then(|x| x.error_code))
-                            }
-                        },
-                        Some(Cow::Borrowed(str)) => {
-                            ErrorFrameData::decode_static(info, Some(MessageContainer::Static(str)))
-                        }
-                        Some(Cow::Owned(str)) => ErrorFrameData::decode_static(
-                            info,
-                            Some(MessageContainer::Formatted(str.clone())),
-                        ),
-                    },
-                    location: Some(frame.location.into()),
-                });
+        while self.front < self.back {
+            let slot = self.front;
+            self.front += 1;
+            if let Some(frame) = self.frame_at(slot) {
+                return Some(frame);
             }
+        }
+        None
+    }
 
-            if self.phase == FrameLoopPhase::LocationMismatchFrame {
-                self.phase = FrameLoopPhase::Ended;
-
-                let location = DecodedLocation::from(frame.location);
-                let origin = match &frame.static_info {
-                    ErrorOrigin::StaticOrigin(origin) => origin.location,
-                    ErrorOrigin::TypeOrigin(_, origin) => origin.and_then(|x| x.location),
-                };
-                if let Some(origin) = origin {
-                    if !origin.is_same(location) {
-                        return Some(ErrorFrameImpl {
-                            data: ErrorFrameData::InternalContext(
-                                InternalContextType::ErrorTypeConstructed,
-                            ),
-                            location: Some(*origin),
-                        });
-                    }
-                }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+impl DoubleEndedIterator for ErrorImplIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while self.front < self.back {
+            self.back -= 1;
+            if let Some(frame) = self.frame_at(self.back) {
+                return Some(frame);
             }
-
-            self.idx -= 1;
-            self.phase = FrameLoopPhase::Context;
         }
         None
     }
 }
+impl ExactSizeIterator for ErrorImplIter<'_> {
+    fn len(&self) -> usize {
+        (self.front..self.back)
+            .filter(|&slot| self.has_frame_at(slot))
+            .count()
+    }
+}
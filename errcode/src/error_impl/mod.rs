@@ -1,39 +1,177 @@
 //! This module contains the internal guts of the error type.
 
 use crate::error_code::ErrorCodeInfo;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::sync::Arc;
 use core::fmt::{Arguments, Display, Formatter};
 use core::panic::Location;
 
 /// Common trait for [`ErrorImpl`] variants.
 pub trait ErrorImplFunctions: Clone {
     /// The iterator type used to iterate frames.
-    type FrameIter<'a>: Iterator<Item = ErrorFrameImpl> + 'a
+    type FrameIter<'a>: ExactSizeIterator<Item = ErrorFrameImpl> + 'a
     where Self: 'a;
 
     /// Creates a new error type.
-    fn new(source: ErrorOrigin, args: Option<&Arguments<'_>>) -> ErrorImpl;
+    fn new(source: ErrorOrigin, args: Option<&Arguments<'_>>) -> Self;
 
-    /// Pushes a new context frame onto this type.
-    fn push_context(&mut self, source: &'static ErrorInfoImpl, args: Option<&Arguments<'_>>);
+    /// Pushes a new context frame onto this type, returning whether it was stored.
+    ///
+    /// Representations with limited room may drop the context instead, marking frames as omitted.
+    fn push_context(
+        &mut self,
+        source: &'static ErrorInfoImpl,
+        args: Option<&Arguments<'_>>,
+    ) -> bool;
+
+    /// Places frames for the [`source`](core::error::Error::source) chain of a converted error
+    /// underneath the frames of this error.
+    ///
+    /// Does nothing if the representation cannot store the chain.
+    fn push_sources(&mut self, error: &dyn core::error::Error);
+
+    /// Adds a context frame with only the location of the caller.
+    fn push_marker(&mut self);
 
     /// Gets the current error code of this type.
     fn code(&self) -> Option<&'static ErrorCodeInfo>;
 
+    /// Gets the error code of the deepest frame that has one.
+    fn root_code(&self) -> Option<&'static ErrorCodeInfo>;
+
+    /// Returns whether the original error was converted from a foreign type.
+    fn is_conversion(&self) -> bool;
+
+    /// Returns the name of the foreign type the original error was converted from.
+    ///
+    /// Returns `None` if the representation no longer stores the type name.
+    fn origin_type_name(&self) -> Option<&'static str>;
+
+    /// Pins the current error code, so later context cannot change it.
+    ///
+    /// Does nothing if the representation cannot store this.
+    fn freeze_code(&mut self);
+
+    /// Clears the current error code, and any freeze on it, returning the code.
+    ///
+    /// The code is still shown in the frames it came from, and the next context with a code
+    /// becomes the current code.
+    fn take_code(&mut self) -> Option<&'static ErrorCodeInfo>;
+
+    /// Replaces the type name of an error converted from a foreign type.
+    ///
+    /// Does nothing if the representation does not store the type name.
+    fn retype_origin(&mut self, name: &'static str);
+
+    /// Places the frames of another error underneath the frames of this error.
+    ///
+    /// Representations that cannot store both chains keep as much of them as they can.
+    fn graft_cause(&mut self, cause: Self);
+
+    /// Removes all but the `len` innermost steps of this error, recomputing its current code.
+    ///
+    /// This may leave an error without any steps, which must still display a placeholder frame.
+    /// Representations that cannot remove steps leave the error unchanged.
+    fn truncate(&mut self, len: usize);
+
+    /// Forgets the locations recorded with [`Location::caller`], so frames use the locations
+    /// stored in their [`ErrorInfoImpl`] instead.
+    #[cfg(feature = "testing")]
+    fn clear_caller_locations(&mut self);
+
+    /// Replaces the location of each frame with the result of a function.
+    ///
+    /// Representations that do not store locations separately from their static infos leave the
+    /// locations unchanged.
+    fn rewrite_locations<F: Fn(DecodedLocation) -> DecodedLocation>(&mut self, f: F);
+
+    /// Replaces the message of each frame with the result of a function.
+    ///
+    /// Representations that only store static messages leave the messages unchanged.
+    fn map_messages<F: Fn(&str) -> String>(&mut self, f: F);
+
+    /// Replaces the message of the outermost frame, returning whether it was replaced.
+    ///
+    /// Representations that only store static messages cannot replace a message with one created
+    /// at runtime, or replace the message of a frame that is not a context.
+    fn replace_message(&mut self, message: Cow<'static, str>) -> bool;
+
+    /// Attaches an arbitrary displayable value to this error.
+    ///
+    /// Representations that cannot store attachments drop the value.
+    fn attach<D: Display + Send + Sync + 'static>(&mut self, attachment: D);
+
+    /// Returns the values attached to this error.
+    fn attachments(&self) -> &[Attachment];
+
+    /// Adds an informational note to this error, kept apart from its frames.
+    ///
+    /// Representations that cannot store notes drop the note.
+    fn push_note(&mut self, note: &'static str);
+
+    /// Returns the notes added to this error, in the order they were added.
+    fn notes(&self) -> &[&'static str];
+
+    /// Gets the location the original error was created at, if known.
+    fn source_location(&self) -> Option<DecodedLocation>;
+
+    /// Returns the frame the original error was created with, skipping frames added for its
+    /// source chain and by the representation.
+    fn origin_frame(&self) -> Option<ErrorFrameImpl>;
+
     /// Returns an iterator of the frames in this error type.
     fn iter<'a>(&'a self) -> Self::FrameIter<'a>;
+
+    /// Panics if the internal invariants of this type do not hold.
+    ///
+    /// This is checked after every operation when built with `--cfg fuzzing`.
+    fn assert_invariants(&self);
 }
 
+/// A value attached to an error with [`ErrorImplFunctions::attach`].
+pub type Attachment = Arc<dyn Display + Send + Sync>;
+
 #[derive(Copy, Clone)]
-#[repr(align(4))]
+#[repr(align(8))]
 pub struct ErrorInfoImpl {
     pub error_code: Option<&'static ErrorCodeInfo>,
     pub message_static: StaticMessageInfo,
     pub location: Option<&'static DecodedLocation>,
 }
 impl ErrorInfoImpl {
+    /// Creates a new object from its parts.
+    pub const fn new(
+        error_code: Option<&'static ErrorCodeInfo>,
+        message_static: StaticMessageInfo,
+        location: Option<&'static DecodedLocation>,
+    ) -> Self {
+        ErrorInfoImpl { error_code, message_static, location }
+    }
+
+    /// Creates a new object containing only a message, which is not treated as a format string.
+    pub const fn new_message(message: &'static str) -> Self {
+        ErrorInfoImpl::new(None, StaticMessageInfo::NoFormat(message), None)
+    }
+
+    /// Creates a new object containing only an error code.
+    pub const fn new_code(code: &'static ErrorCodeInfo) -> Self {
+        ErrorInfoImpl::new(Some(code), StaticMessageInfo::None, None)
+    }
+
+    /// Returns `true` if this object contains a static message.
+    pub fn has_message(&self) -> bool {
+        !matches!(self.message_static, StaticMessageInfo::None)
+    }
+
+    /// Returns `true` if this object contains an error code.
+    pub fn has_code(&self) -> bool {
+        self.error_code.is_some()
+    }
+
     /// Returns `true` if the only information in this object is the error code itself.
     pub fn is_code_only(&self) -> bool {
-        self.location.is_none()
+        self.has_code() && !self.has_message() && self.location.is_none()
     }
 }
 
@@ -44,23 +182,86 @@ pub enum StaticMessageInfo {
     None,
 }
 
+/// A source code location stored in an error.
 #[derive(Copy, Clone, Debug)]
 pub struct DecodedLocation {
+    /// The file the location is in.
+    ///
+    /// This is empty if the file was not stored, such as with the `compact_location` feature.
     pub module: &'static str,
+
+    /// The line number of the location.
     pub line: u32,
+
+    /// The column number of the location.
     pub column: u32,
 }
-impl From<&'static Location<'static>> for DecodedLocation {
-    fn from(value: &'static Location<'static>) -> Self {
-        DecodedLocation { module: value.file(), line: value.line(), column: value.column() }
+impl From<&Location<'static>> for DecodedLocation {
+    fn from(value: &Location<'static>) -> Self {
+        DecodedLocation::from_location(value)
     }
 }
 impl DecodedLocation {
-    fn is_same(&self, other: DecodedLocation) -> bool {
-        self.module == other.module && self.line == other.line
+    /// Copies out the file, line and column of a location.
+    ///
+    /// Only the file name needs to be `'static`, so the location itself may be borrowed for any
+    /// lifetime.
+    pub fn from_location(location: &Location<'static>) -> Self {
+        DecodedLocation {
+            module: location.file(),
+            line: location.line(),
+            column: location.column(),
+        }
+    }
+
+    /// Returns whether two locations refer to the same line.
+    ///
+    /// An empty module (as stored by `compact_location`) matches any module.
+    pub(crate) fn is_same(&self, other: DecodedLocation) -> bool {
+        (self.module == other.module || self.module.is_empty() || other.module.is_empty())
+            && self.line == other.line
+    }
+
+    /// Returns whether two locations are close enough that an error created at one does not need
+    /// a frame noting where its [`ErrorInfo`](crate::ErrorInfo) was created at the other.
+    ///
+    /// See [`LOCATION_THRESHOLD`].
+    // the threshold is only `0` when it is not configured
+    #[allow(clippy::absurd_extreme_comparisons)]
+    pub(crate) fn is_near(&self, other: DecodedLocation) -> bool {
+        (self.module == other.module || self.module.is_empty() || other.module.is_empty())
+            && self.line.abs_diff(other.line) <= LOCATION_THRESHOLD
     }
 }
 
+/// The number of bytes a message formatted at runtime is truncated to with the `heapless` feature.
+#[cfg(feature = "heapless")]
+pub const HEAPLESS_MESSAGE_CAPACITY: usize = 64;
+
+/// The number of steps an error is limited to with the `heapless` feature.
+///
+/// The steps are stored inline in the heap allocation that backs each error, so its size grows
+/// with this capacity.
+///
+/// Each context or cause pushed beyond this replaces the outermost step, as when an error grows
+/// too deep without the feature, and a frame noting that frames were omitted is shown.
+#[cfg(feature = "heapless")]
+pub const HEAPLESS_STEP_CAPACITY: usize = 16;
+
+/// How many lines apart an error and the [`ErrorInfo`](crate::ErrorInfo) it was created from may
+/// be before a frame noting where the `ErrorInfo` was created is shown.
+///
+/// This is `0` by default, so the frame is shown whenever the lines differ. It can be raised by
+/// setting the `ERRCODE_LOCATION_THRESHOLD` environment variable when building, which reduces
+/// noise when formatting splits a call across lines.
+pub const LOCATION_THRESHOLD: u32 = match option_env!("ERRCODE_LOCATION_THRESHOLD") {
+    Some(value) => match u32::from_str_radix(value, 10) {
+        Ok(lines) => lines,
+        Err(_) => panic!("`ERRCODE_LOCATION_THRESHOLD` must be a number of lines"),
+    },
+    None => 0,
+};
+
 #[derive(Copy, Clone)]
 pub enum ErrorOrigin {
     StaticOrigin(&'static ErrorInfoImpl),
@@ -75,43 +276,67 @@ pub struct ErrorFrameImpl {
 }
 impl Display for ErrorFrameImpl {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.fmt_with(f, true, true)
+    }
+}
+
+impl ErrorFrameImpl {
+    /// Formats this frame, optionally leaving out its error code and location.
+    ///
+    /// Frames that only have an error code always show it, as they have nothing else to show,
+    /// unless the `humanize_codes` feature is enabled. That feature shows the variant name of the
+    /// code as a message instead, such as `disk full` for `DiskFull`.
+    pub fn fmt_with(
+        &self,
+        f: &mut Formatter<'_>,
+        show_codes: bool,
+        show_locations: bool,
+    ) -> core::fmt::Result {
         match &self.data {
             ErrorFrameData::InternalContext(ctx) => write!(f, "{}", ctx.message())?,
             ErrorFrameData::TypeFrame(ty, info) => match info {
-                Some(info) if info.message.is_some() => write!(
-                    f,
-                    "{} ({}::{})",
-                    info.message.unwrap(),
-                    info.type_name,
-                    info.variant_name
-                )?,
-                Some(info) => {
-                    write!(f, "<from type: {}> ({}::{})", ty, info.type_name, info.variant_name)?
+                Some(info) if info.message.is_some() => {
+                    write!(f, "{}", info.message.unwrap())?;
+                    if show_codes {
+                        write!(f, " ({}::{})", info.type_name, info.variant_name)?;
+                    }
                 }
-                None => write!(f, "<from type: {}>", ty)?,
+                Some(info) if show_codes => write!(f, "<from type: {}> ({})", ty, info)?,
+                _ => write!(f, "<from type: {}>", ty)?,
             },
+            ErrorFrameData::ConvertedFrame(_, msg, info) => {
+                write!(f, "{msg}")?;
+                if show_codes && let Some(info) = info {
+                    write!(f, " ({}::{})", info.type_name, info.variant_name)?;
+                }
+            }
             ErrorFrameData::NormalFrame(msg, info) => match info {
-                Some(info) if info.message.is_some() && msg.is_none() => write!(
-                    f,
-                    "{} ({}::{})",
-                    info.message.unwrap(),
-                    info.type_name,
-                    info.variant_name
-                )?,
-                Some(info) if msg.is_some() => write!(
-                    f,
-                    "{} ({}::{})",
-                    msg.as_ref().unwrap(),
-                    info.type_name,
-                    info.variant_name
-                )?,
-                Some(info) => write!(f, "{}::{}", info.type_name, info.variant_name)?,
+                Some(info) if msg.is_some() || info.message.is_some() => {
+                    match msg {
+                        Some(msg) => write!(f, "{msg}")?,
+                        None => write!(f, "{}", info.message.unwrap())?,
+                    }
+                    if show_codes {
+                        write!(f, " ({}::{})", info.type_name, info.variant_name)?;
+                    }
+                }
+                #[cfg(feature = "humanize_codes")]
+                Some(info) => {
+                    write!(f, "{}", HumanizedName(info.variant_name))?;
+                    if show_codes {
+                        write!(f, " ({}::{})", info.type_name, info.variant_name)?;
+                    }
+                }
+                #[cfg(not(feature = "humanize_codes"))]
+                Some(info) => write!(f, "{}", info)?,
                 None if msg.is_some() => write!(f, "{}", msg.as_ref().unwrap())?,
+                // frames added by `Error::mark` only have a location
+                None if self.location.is_some() => write!(f, "<reached here>")?,
                 None => write!(f, "<internal error: no message or code given???>")?,
             },
         }
 
-        if let Some(location) = &self.location {
+        if show_locations && let Some(location) = &self.location {
             write!(f, " [at {}:{}:{}]", location.module, location.line, location.column)?;
         }
 
@@ -119,6 +344,50 @@ impl Display for ErrorFrameImpl {
     }
 }
 
+impl ErrorFrameImpl {
+    /// Returns the error code attached to this frame, if any.
+    pub fn code(&self) -> Option<&'static ErrorCodeInfo> {
+        match &self.data {
+            ErrorFrameData::InternalContext(_) => None,
+            ErrorFrameData::TypeFrame(_, code) => *code,
+            ErrorFrameData::ConvertedFrame(_, _, code) => *code,
+            ErrorFrameData::NormalFrame(_, code) => *code,
+        }
+    }
+
+    /// Returns the name of the foreign type this frame was converted from, if any.
+    pub fn type_name(&self) -> Option<&'static str> {
+        match &self.data {
+            ErrorFrameData::TypeFrame(ty, _) | ErrorFrameData::ConvertedFrame(ty, ..) => Some(ty),
+            _ => None,
+        }
+    }
+
+    /// Returns whether this frame was added by the representation rather than by the user, such
+    /// as to note that frames were omitted.
+    pub fn is_internal(&self) -> bool {
+        matches!(self.data, ErrorFrameData::InternalContext(_))
+    }
+
+    /// Returns the location this frame was created at, if known.
+    pub fn location(&self) -> Option<DecodedLocation> {
+        self.location
+    }
+
+    /// Returns the message of this frame, falling back to the message of its error code.
+    pub fn message(&self) -> Option<&str> {
+        match &self.data {
+            ErrorFrameData::InternalContext(ctx) => Some(ctx.message()),
+            ErrorFrameData::TypeFrame(_, code) => code.and_then(|x| x.message),
+            ErrorFrameData::ConvertedFrame(_, msg, _) => Some(msg.as_str()),
+            ErrorFrameData::NormalFrame(msg, code) => match msg {
+                Some(msg) => Some(msg.as_str()),
+                None => code.and_then(|x| x.message),
+            },
+        }
+    }
+}
+
 /// The data represented by an error frame.
 #[derive(Clone, Debug)]
 enum ErrorFrameData {
@@ -130,6 +399,11 @@ enum ErrorFrameData {
     /// error.
     TypeFrame(&'static str, Option<&'static ErrorCodeInfo>),
 
+    /// Used to represent a frame converted from an error of a foreign type, with the message
+    /// formatted from it. Only `repr_full` stores these messages.
+    #[cfg_attr(not(feature = "repr_full"), allow(dead_code))]
+    ConvertedFrame(&'static str, MessageContainer, Option<&'static ErrorCodeInfo>),
+
     /// A normal frame that contains a message, an error code or both.
     NormalFrame(Option<MessageContainer>, Option<&'static ErrorCodeInfo>),
 }
@@ -159,16 +433,48 @@ enum MessageContainer {
     /// Used to represent a static message that couldn't be formatted.
     IncompleteStatic(&'static str),
 
-    #[cfg(feature = "repr_full")]
-    Formatted(alloc::string::String),
+    /// Used to represent a message formatted at runtime. This is reference counted so that
+    /// frames can be decoded without copying the message.
+    #[cfg(all(feature = "repr_full", not(feature = "heapless")))]
+    Formatted(Arc<str>),
+
+    /// Used to represent a message formatted at runtime, truncated to a fixed capacity.
+    #[cfg(feature = "heapless")]
+    Formatted(heapless::String<HEAPLESS_MESSAGE_CAPACITY>),
 }
 impl MessageContainer {
+    /// Formats a message at runtime.
+    #[cfg(all(feature = "repr_full", not(feature = "heapless")))]
+    fn formatted(args: Arguments<'_>) -> Self {
+        MessageContainer::Formatted(alloc::string::ToString::to_string(&args).into())
+    }
+
+    /// Formats a message at runtime, truncating it to [`HEAPLESS_MESSAGE_CAPACITY`] bytes.
+    #[cfg(feature = "heapless")]
+    fn formatted(args: Arguments<'_>) -> Self {
+        /// Writes as much of the message as fits, ending at a character boundary.
+        struct TruncatingWriter(heapless::String<HEAPLESS_MESSAGE_CAPACITY>);
+        impl core::fmt::Write for TruncatingWriter {
+            fn write_str(&mut self, s: &str) -> core::fmt::Result {
+                let mut len = s.len().min(HEAPLESS_MESSAGE_CAPACITY - self.0.len());
+                while !s.is_char_boundary(len) {
+                    len -= 1;
+                }
+                self.0.push_str(&s[..len]).map_err(|_| core::fmt::Error)
+            }
+        }
+
+        let mut writer = TruncatingWriter(heapless::String::new());
+        let _ = core::fmt::Write::write_fmt(&mut writer, args);
+        MessageContainer::Formatted(writer.0)
+    }
+
     fn as_str(&self) -> &str {
         match self {
             MessageContainer::Static(v) => v,
             MessageContainer::IncompleteStatic(v) => v,
             #[cfg(feature = "repr_full")]
-            MessageContainer::Formatted(v) => v.as_str(),
+            MessageContainer::Formatted(v) => v,
         }
     }
 
@@ -186,6 +492,45 @@ impl Display for MessageContainer {
     }
 }
 
+/// Displays the name of an error code variant as a lowercase message, such as `disk full` for
+/// `DiskFull` or `io error` for `IOError`.
+#[cfg(feature = "humanize_codes")]
+struct HumanizedName(&'static str);
+#[cfg(feature = "humanize_codes")]
+impl Display for HumanizedName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        use core::fmt::Write;
+
+        let mut chars = self.0.chars().peekable();
+        let mut prev: Option<char> = None;
+        while let Some(ch) = chars.next() {
+            if ch == '_' {
+                if prev.is_some_and(|x| x != ' ') {
+                    f.write_char(' ')?;
+                    prev = Some(' ');
+                }
+                continue;
+            }
+            // a word starts at an uppercase letter following a lowercase letter or digit, or at
+            // the last uppercase letter of an acronym followed by a lowercase letter
+            let starts_word = ch.is_uppercase()
+                && prev.is_some_and(|x| {
+                    x.is_lowercase()
+                        || x.is_ascii_digit()
+                        || (x.is_uppercase() && chars.peek().is_some_and(|x| x.is_lowercase()))
+                });
+            if starts_word {
+                f.write_char(' ')?;
+            }
+            for ch in ch.to_lowercase() {
+                f.write_char(ch)?;
+            }
+            prev = Some(ch);
+        }
+        Ok(())
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Clone, Debug)]
 enum InternalContextType {
@@ -201,8 +546,13 @@ enum InternalContextType {
     OriginalTypeLost,
 
     /// Used to note to the user that additional frames of context may have been omitted from the
-    /// trace. This occurs on the compact representation used when `alloc` isn't set.
+    /// trace. This occurs on the compact representation used when `alloc` isn't set, and on the
+    /// full representation when an unreasonable amount of context is added.
     FurtherFramesOmitted,
+
+    /// Used to separate the frames of an error from the frames of another error attached as its
+    /// cause.
+    CauseBoundary,
 }
 impl InternalContextType {
     fn message(&self) -> &'static str {
@@ -210,6 +560,7 @@ impl InternalContextType {
             InternalContextType::ErrorTypeConstructed => "<ErrorInfo constructed>",
             InternalContextType::OriginalTypeLost => "<original error type lost>",
             InternalContextType::FurtherFramesOmitted => "<some frames have been omitted>",
+            InternalContextType::CauseBoundary => "<start of attached cause>",
         }
     }
 }
@@ -232,6 +583,7 @@ pub use full::ErrorImpl;
 #[cfg(any(
     feature = "repr_unboxed",
     feature = "repr_unboxed_location",
+    feature = "compact_location",
     not(any(feature = "repr_full"))
 ))]
 mod unboxed;
@@ -239,6 +591,7 @@ mod unboxed;
 #[cfg(any(
     feature = "repr_unboxed",
     feature = "repr_unboxed_location",
+    feature = "compact_location",
     not(any(feature = "repr_full"))
 ))]
 pub use unboxed::ErrorImpl;
@@ -255,3 +608,16 @@ const _: () = {
         "You may only use one of `repr_full`, `repr_unboxed` or `repr_unboxed_location`."
     );
 };
+
+#[cfg(all(
+    feature = "compact_location",
+    any(feature = "repr_full", feature = "repr_unboxed_location")
+))]
+const _: () = {
+    compile_error!("`compact_location` may only be used with `repr_unboxed`.");
+};
+
+#[cfg(all(feature = "compact_location", not(target_pointer_width = "64")))]
+const _: () = {
+    compile_error!("`compact_location` is only supported on 64-bit targets.");
+};
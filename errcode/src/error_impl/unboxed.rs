@@ -1,58 +1,295 @@
-//! Implementation for `repr_unboxed` and `repr_unboxed_location`.
+//! Implementation for `repr_unboxed`, `repr_unboxed_location` and `compact_location`.
 //!
 //! TODO: Document
 
 use super::*;
+use crate::intern::InternTable;
 use core::hint::unreachable_unchecked;
 use core::num::NonZeroUsize;
 
 #[derive(Clone)]
 pub struct ErrorImpl {
     origin_info: PackedOriginInfo,
+    /// The location the error was created at, or `None` if it was cleared for testing.
     #[cfg(feature = "repr_unboxed_location")]
-    original_location: &'static Location<'static>,
+    original_location: Option<&'static Location<'static>>,
 }
 impl ErrorImplFunctions for ErrorImpl {
     type FrameIter<'a> = ErrorImplIter;
 
-    #[cfg_attr(feature = "repr_unboxed_location", track_caller)]
+    #[cfg_attr(
+        any(feature = "repr_unboxed_location", feature = "compact_location"),
+        track_caller
+    )]
     #[inline(never)]
-    fn new(source: ErrorOrigin, _args: Option<&Arguments<'_>>) -> ErrorImpl {
-        ErrorImpl {
-            origin_info: PackedOriginInfo::for_origin(source),
+    fn new(source: ErrorOrigin, _args: Option<&Arguments<'_>>) -> Self {
+        #[cfg(feature = "compact_location")]
+        let origin_info = PackedOriginInfo::for_origin(source).with_location(Location::caller());
+        #[cfg(not(feature = "compact_location"))]
+        let origin_info = PackedOriginInfo::for_origin(source);
+
+        let error = ErrorImpl {
+            origin_info,
             #[cfg(feature = "repr_unboxed_location")]
-            original_location: Location::caller(),
-        }
+            original_location: Some(Location::caller()),
+        };
+        #[cfg(fuzzing)]
+        error.assert_invariants();
+        error
     }
 
     #[inline(never)]
-    fn push_context(&mut self, source: &'static ErrorInfoImpl, _args: Option<&Arguments<'_>>) {
+    fn push_context(
+        &mut self,
+        source: &'static ErrorInfoImpl,
+        _args: Option<&Arguments<'_>>,
+    ) -> bool {
+        let kept = self.origin_info.keeps_context(source);
         self.origin_info = self.origin_info.with_context(source);
+        #[cfg(fuzzing)]
+        self.assert_invariants();
+        kept
+    }
+
+    fn push_sources(&mut self, _error: &dyn core::error::Error) {}
+
+    #[track_caller]
+    fn push_marker(&mut self) {
+        self.push_context(crate::intern::marker_info(Location::caller()), None);
     }
 
     fn code(&self) -> Option<&'static ErrorCodeInfo> {
         self.origin_info.code()
     }
 
+    fn root_code(&self) -> Option<&'static ErrorCodeInfo> {
+        self.origin_info.root_code()
+    }
+
+    fn is_conversion(&self) -> bool {
+        self.origin_info.tag() != TAG_STATIC_ORIGINAL
+    }
+
+    fn origin_type_name(&self) -> Option<&'static str> {
+        self.origin_info.type_name()
+    }
+
+    fn freeze_code(&mut self) {
+        self.origin_info = self.origin_info.with_frozen_code();
+        #[cfg(fuzzing)]
+        self.assert_invariants();
+    }
+
+    fn take_code(&mut self) -> Option<&'static ErrorCodeInfo> {
+        let code = self.origin_info.code();
+        self.origin_info = self.origin_info.with_taken_code();
+        #[cfg(fuzzing)]
+        self.assert_invariants();
+        code
+    }
+
+    fn retype_origin(&mut self, name: &'static str) {
+        if self.origin_info.type_name().is_some() {
+            self.origin_info = self.origin_info.with_type_name(name);
+        }
+        #[cfg(fuzzing)]
+        self.assert_invariants();
+    }
+
+    fn graft_cause(&mut self, cause: Self) {
+        if let Some(origin_info) = self.origin_info.graft_onto(cause.origin_info) {
+            self.origin_info = origin_info;
+            #[cfg(feature = "repr_unboxed_location")]
+            {
+                self.original_location = cause.original_location;
+            }
+        }
+        #[cfg(fuzzing)]
+        self.assert_invariants();
+    }
+
+    fn truncate(&mut self, _len: usize) {}
+
+    fn rewrite_locations<F: Fn(DecodedLocation) -> DecodedLocation>(&mut self, _f: F) {}
+
+    #[cfg(feature = "testing")]
+    fn clear_caller_locations(&mut self) {
+        #[cfg(feature = "repr_unboxed_location")]
+        {
+            self.original_location = None;
+        }
+        #[cfg(feature = "compact_location")]
+        {
+            self.origin_info = self.origin_info.without_location();
+        }
+    }
+
+    fn map_messages<F: Fn(&str) -> String>(&mut self, _f: F) {}
+
+    fn replace_message(&mut self, message: Cow<'static, str>) -> bool {
+        let Cow::Borrowed(message) = message else {
+            return false;
+        };
+        let replace = |info: &'static ErrorInfoImpl| {
+            crate::intern::message_info(info.error_code, message, info.location)
+        };
+        match self.origin_info.with_outermost_context(replace) {
+            Some(origin_info) => {
+                self.origin_info = origin_info;
+                #[cfg(fuzzing)]
+                self.assert_invariants();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn attach<D: Display + Send + Sync + 'static>(&mut self, _attachment: D) {}
+
+    fn attachments(&self) -> &[Attachment] {
+        &[]
+    }
+
+    fn push_note(&mut self, _note: &'static str) {}
+
+    fn notes(&self) -> &[&'static str] {
+        &[]
+    }
+
+    fn source_location(&self) -> Option<DecodedLocation> {
+        if self.origin_info.is_original() {
+            self.original_location()
+                .or_else(|| self.origin_info.context_first().location.copied())
+        } else {
+            self.original_location()
+        }
+    }
+
+    fn origin_frame(&self) -> Option<ErrorFrameImpl> {
+        self.iter().filter(|x| !x.is_internal()).last()
+    }
+
     fn iter(&self) -> Self::FrameIter<'_> {
         ErrorImplIter {
             phase: ErrorIterPhase::LastContext,
             origin_info: self.origin_info,
-            #[cfg(feature = "repr_unboxed_location")]
-            original_location: Some(self.original_location),
-            #[cfg(not(feature = "repr_unboxed_location"))]
-            original_location: None,
+            original_location: self.original_location(),
         }
     }
+
+    fn assert_invariants(&self) {
+        self.origin_info.assert_invariants();
+    }
+}
+impl ErrorImpl {
+    /// Returns the location the error was created at, if it is stored.
+    fn original_location(&self) -> Option<DecodedLocation> {
+        #[cfg(feature = "repr_unboxed_location")]
+        return self.original_location.map(DecodedLocation::from);
+        #[cfg(feature = "compact_location")]
+        return self.origin_info.location();
+        #[cfg(not(any(feature = "repr_unboxed_location", feature = "compact_location")))]
+        return None;
+    }
 }
 
 const TAG_STATIC_ORIGINAL: usize = 0;
 const TAG_STATIC_TYPE_ONLY: usize = 1;
 const TAG_STATIC_CONTEXT_ONLY: usize = 2;
+/// A conversion from a foreign type that was mapped to an error code. This is stored the same way
+/// as `TAG_STATIC_ORIGINAL`, except that the first context is a [`TypedCodeInfo`].
+const TAG_STATIC_TYPED_ORIGINAL: usize = 3;
 const TAG_MASK: usize = 0b11;
+/// Set in `tag` when the second context has an error code, so it can be checked without loading
+/// the context.
+const SECOND_CODED_BIT_MASK: usize = 0b100;
+const TAG_BITS_MASK: usize = TAG_MASK | SECOND_CODED_BIT_MASK;
 
-const MAX_TYPE_LEN: usize = (usize::MAX >> 2) + 1;
+/// The exclusive upper bound on the length of a type name stored in a `TAG_STATIC_TYPE_ONLY`
+/// value.
+const MAX_TYPE_LEN: usize = max_type_len(POINTER_MASK);
 const OMITTED_BIT_MASK: usize = 0b1;
+const FROZEN_BIT_MASK: usize = 0b10;
+const TAKEN_BIT_MASK: usize = 0b100;
+const FLAG_BITS_MASK: usize = OMITTED_BIT_MASK | FROZEN_BIT_MASK | TAKEN_BIT_MASK;
+
+/// Returns the exclusive upper bound on type name lengths for a given pointer mask.
+const fn max_type_len(mask: usize) -> usize {
+    (mask >> 2) + 1
+}
+
+/// Packs the length of a type name into the bits of `tag` above [`TAG_MASK`].
+const fn pack_type_len(len: usize) -> usize {
+    (len << 2) | TAG_STATIC_TYPE_ONLY
+}
+
+/// Unpacks the length of a type name packed by [`pack_type_len`].
+const fn unpack_type_len(tag: usize, mask: usize) -> usize {
+    (tag & mask) >> 2
+}
+
+/// Returns whether type names of a range of lengths, up to the longest allowed, survive packing
+/// with a given pointer mask.
+const fn type_len_round_trips(mask: usize) -> bool {
+    let max = max_type_len(mask) - 1;
+    let lens = [0, 1, 2, 3, 4, 255, 256, max / 2, max - 1, max];
+    let mut i = 0;
+    while i < lens.len() {
+        let len = if lens[i] < max { lens[i] } else { max };
+        let packed = pack_type_len(len);
+        if packed & mask != packed
+            || packed & TAG_MASK != TAG_STATIC_TYPE_ONLY
+            || unpack_type_len(packed, mask) != len
+        {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+// checks the type length packing for the current target, and for the layouts of 32-bit and 16-bit
+// targets.
+const _: () = {
+    assert!(type_len_round_trips(POINTER_MASK));
+    assert!(type_len_round_trips(u32::MAX as usize));
+    assert!(type_len_round_trips(u16::MAX as usize));
+    assert!(max_type_len(u32::MAX as usize) == 1 << 30);
+};
+
+/// Whether `compact_location` stores locations in the upper bits of pointers on this target.
+///
+/// This is only done on x86-64, where user space addresses are below the 48-bit boundary unless
+/// linear address masking is enabled. Other targets may tag the upper bits of heap pointers, such
+/// as with top-byte ignore and memory tagging on AArch64, so `compact_location` falls back to the
+/// encoding of `repr_unboxed` there, and no locations are stored.
+#[cfg(feature = "compact_location")]
+const COMPACT_LOCATIONS: bool = cfg!(target_arch = "x86_64");
+
+/// The bits of `tag` and `additional` that are used to store pointers and lengths.
+///
+/// With `compact_location`, the upper 16 bits of each word are used to store the line and column
+/// the error was created at instead, if [`COMPACT_LOCATIONS`] is set. This relies on pointers
+/// being below the 48-bit address boundary, which is checked when they are stored.
+#[cfg(feature = "compact_location")]
+const POINTER_MASK: usize = if COMPACT_LOCATIONS {
+    (1 << LOCATION_SHIFT) - 1
+} else {
+    usize::MAX
+};
+#[cfg(not(feature = "compact_location"))]
+const POINTER_MASK: usize = usize::MAX;
+
+/// Returns whether a pointer or length can be stored without clobbering the location bits.
+#[inline(always)]
+fn fits_pointer_mask(value: usize) -> bool {
+    value & POINTER_MASK == value
+}
+
+#[cfg(feature = "compact_location")]
+const LOCATION_SHIFT: u32 = 48;
+#[cfg(feature = "compact_location")]
+const MAX_COMPACT_LOCATION: u32 = u16::MAX as u32;
 
 #[derive(Copy, Clone)]
 struct PackedOriginInfo {
@@ -62,26 +299,36 @@ struct PackedOriginInfo {
     /// This is nonzero to allow for niche optimization on the error type.
     ///
     /// For `TAG_STATIC_ORIGINAL` and `TAG_STATIC_CONTEXT_ONLY`, this is a pointer to an
-    /// `ErrorSourceStatic`. It is enforced nonzero because pointers cannot be zero.
+    /// `ErrorSourceStatic`. It is enforced nonzero because pointers cannot be zero. The bit above
+    /// the tag caches whether the second context in `additional` has an error code.
+    ///
+    /// For `TAG_STATIC_TYPED_ORIGINAL`, this is the same, except that the pointer is to the info
+    /// of a [`TypedCodeInfo`].
     ///
     /// For `TAG_STATIC_TYPE_ONLY`, this is the length of the type string, with the pointer
     /// itself stored in `additional`. It is enforced nonzero because the tag is nonzero.
+    ///
+    /// With `compact_location`, the bits outside of `POINTER_MASK` store the line number.
     tag: NonZeroUsize,
 
     /// Additional tag information.
     ///
-    /// For `TAG_STATIC_ORIGINAL` and `TAG_STATIC_CONTEXT_ONLY` this is a pointer to an
-    /// `ErrorSourceStatic`, or zero (for no latest context). The lowest bit is used to store
-    /// a flag for whether frames have been omitted from this context.
+    /// For the other tags, this is a pointer to an `ErrorSourceStatic`, or zero (for no latest
+    /// context). The lowest bit is used to store a flag for whether frames have been omitted from
+    /// this context, the next bit a flag for whether the error code has been frozen, and the bit
+    /// after that a flag for whether the error code has been taken.
     ///
     /// For `TAG_STATIC_TYPE_ONLY`, this is the pointer to the string.
+    ///
+    /// With `compact_location`, the bits outside of `POINTER_MASK` store the column number.
     additional: usize,
 }
 impl PackedOriginInfo {
     fn for_origin(e: ErrorOrigin) -> Self {
         unsafe {
             match e {
-                ErrorOrigin::StaticOrigin(ptr) | ErrorOrigin::TypeOrigin(_, Some(ptr)) => {
+                ErrorOrigin::StaticOrigin(ptr) => {
+                    assert!(fits_pointer_mask(ptr as *const _ as usize));
                     PackedOriginInfo {
                         tag: NonZeroUsize::new_unchecked(
                             (ptr as *const _ as usize) | TAG_STATIC_ORIGINAL,
@@ -89,10 +336,21 @@ impl PackedOriginInfo {
                         additional: 0,
                     }
                 }
+                ErrorOrigin::TypeOrigin(name, Some(ptr)) => {
+                    let ptr = &TypedCodeInfo::get(name, ptr).info;
+                    assert!(fits_pointer_mask(ptr as *const _ as usize));
+                    PackedOriginInfo {
+                        tag: NonZeroUsize::new_unchecked(
+                            (ptr as *const _ as usize) | TAG_STATIC_TYPED_ORIGINAL,
+                        ),
+                        additional: 0,
+                    }
+                }
                 ErrorOrigin::TypeOrigin(ptr, None) => {
                     assert!(ptr.len() < MAX_TYPE_LEN);
+                    assert!(fits_pointer_mask(ptr.as_ptr() as usize));
                     PackedOriginInfo {
-                        tag: NonZeroUsize::new_unchecked((ptr.len() << 2) | TAG_STATIC_TYPE_ONLY),
+                        tag: NonZeroUsize::new_unchecked(pack_type_len(ptr.len())),
                         additional: ptr.as_ptr() as usize,
                     }
                 }
@@ -104,41 +362,289 @@ impl PackedOriginInfo {
         self.tag.get() & TAG_MASK
     }
 
+    /// Returns whether this value stores contexts, rather than only a type name.
+    fn has_contexts(&self) -> bool {
+        self.tag() != TAG_STATIC_TYPE_ONLY
+    }
+
+    /// Returns whether the first context is the info the error was created with.
+    fn is_original(&self) -> bool {
+        self.tag() == TAG_STATIC_ORIGINAL || self.tag() == TAG_STATIC_TYPED_ORIGINAL
+    }
+
+    /// Panics if the tag and pointers stored in this value are inconsistent.
+    fn assert_invariants(&self) {
+        match self.tag() {
+            TAG_STATIC_ORIGINAL | TAG_STATIC_CONTEXT_ONLY | TAG_STATIC_TYPED_ORIGINAL => {
+                let first = self.tag.get() & POINTER_MASK & !TAG_BITS_MASK;
+                assert_ne!(first, 0, "context pointer is null");
+                assert!(first.is_multiple_of(align_of::<ErrorInfoImpl>()));
+
+                let second = self.additional & POINTER_MASK & !FLAG_BITS_MASK;
+                assert!(second.is_multiple_of(align_of::<ErrorInfoImpl>()));
+                assert_eq!(
+                    self.is_second_coded(),
+                    self.context_second()
+                        .is_some_and(|x| x.error_code.is_some()),
+                    "cached code flag does not match the second context"
+                );
+                // contexts dropped while the code was frozen are marked even without a second
+                if second == 0 && !self.is_code_frozen() && !self.is_code_taken() {
+                    assert!(!self.has_omitted_context(), "frames omitted without a second context");
+                }
+            }
+            TAG_STATIC_TYPE_ONLY => {
+                let ptr = self.additional & POINTER_MASK;
+                let len = unpack_type_len(self.tag.get(), POINTER_MASK);
+                assert_ne!(ptr, 0, "type name pointer is null");
+                assert!(len < MAX_TYPE_LEN);
+                assert!(core::str::from_utf8(self.ty_name().as_bytes()).is_ok());
+            }
+            tag => panic!("invalid tag: {tag}"),
+        }
+    }
+
+    /// Stores the line and column of a location in the unused upper bits of this value.
+    ///
+    /// Locations with a line that does not fit are not stored at all, and columns that do not fit
+    /// are stored as `0`.
+    #[cfg(feature = "compact_location")]
+    fn with_location(mut self, location: &'static Location<'static>) -> Self {
+        let line = location.line();
+        let column = location.column();
+        if COMPACT_LOCATIONS && line <= MAX_COMPACT_LOCATION {
+            let column = if column <= MAX_COMPACT_LOCATION { column } else { 0 };
+            unsafe {
+                self.tag = NonZeroUsize::new_unchecked(
+                    (self.tag.get() & POINTER_MASK) | ((line as usize) << LOCATION_SHIFT),
+                );
+            }
+            self.additional =
+                (self.additional & POINTER_MASK) | ((column as usize) << LOCATION_SHIFT);
+        }
+        self
+    }
+
+    /// Removes the location stored by [`Self::with_location`], if any.
+    #[cfg(all(feature = "compact_location", feature = "testing"))]
+    fn without_location(mut self) -> Self {
+        unsafe {
+            self.tag = NonZeroUsize::new_unchecked(self.tag.get() & POINTER_MASK);
+        }
+        self.additional &= POINTER_MASK;
+        self
+    }
+
+    /// Returns the location stored by [`Self::with_location`], if any.
+    #[cfg(feature = "compact_location")]
+    fn location(&self) -> Option<DecodedLocation> {
+        if !COMPACT_LOCATIONS {
+            return None;
+        }
+        let line = (self.tag.get() >> LOCATION_SHIFT) as u32;
+        let column = (self.additional >> LOCATION_SHIFT) as u32;
+        if line == 0 {
+            None
+        } else {
+            Some(DecodedLocation { module: "", line, column })
+        }
+    }
+
+    /// Returns whether [`PackedOriginInfo::with_context`] stores a given context, rather than
+    /// dropping it and marking frames as omitted.
+    fn keeps_context(&self, source: &'static ErrorInfoImpl) -> bool {
+        let source_coded = source.error_code.is_some();
+        match self.tag() {
+            TAG_STATIC_TYPE_ONLY => true,
+            // a frozen code may not be replaced, and a coded context is not replaced by one
+            // without a code
+            _ if self.is_code_frozen() && source_coded => false,
+            _ => self.context_second().is_none() || !self.is_second_coded() || source_coded,
+        }
+    }
+
     fn with_context(mut self, source: &'static ErrorInfoImpl) -> Self {
+        assert!(fits_pointer_mask(source as *const _ as usize));
         unsafe {
             match self.tag() {
-                TAG_STATIC_ORIGINAL | TAG_STATIC_CONTEXT_ONLY => {
-                    if self.additional == 0 {
-                        self.additional = source as *const _ as usize;
-                        self
-                    } else {
-                        let original = &*(self.additional as *const ErrorInfoImpl);
-                        if original.error_code.is_none() || source.error_code.is_some() {
-                            self.additional = source as *const _ as usize;
-                            self.additional |= OMITTED_BIT_MASK;
-                            self
-                        } else {
+                TAG_STATIC_ORIGINAL | TAG_STATIC_CONTEXT_ONLY | TAG_STATIC_TYPED_ORIGINAL => {
+                    let source_coded = source.error_code.is_some();
+                    let mut kept_bits = self.additional & (!POINTER_MASK | FLAG_BITS_MASK);
+                    if source_coded {
+                        kept_bits &= !TAKEN_BIT_MASK;
+                    }
+                    match self.context_second() {
+                        _ if !self.keeps_context(source) => {
                             self.additional |= OMITTED_BIT_MASK;
-                            self
+                        }
+                        None => {
+                            self.additional = source as *const _ as usize | kept_bits;
+                            self.set_second_coded(source_coded);
+                        }
+                        Some(_) => {
+                            self.additional =
+                                source as *const _ as usize | kept_bits | OMITTED_BIT_MASK;
+                            self.set_second_coded(source_coded);
                         }
                     }
+                    self
                 }
                 TAG_STATIC_TYPE_ONLY => PackedOriginInfo {
                     tag: NonZeroUsize::new_unchecked(
-                        (source as *const _ as usize) | TAG_STATIC_CONTEXT_ONLY,
+                        (source as *const _ as usize)
+                            | TAG_STATIC_CONTEXT_ONLY
+                            | (self.tag.get() & !POINTER_MASK),
                     ),
-                    additional: 0,
+                    additional: self.additional & !POINTER_MASK,
                 },
                 _ => unreachable_unchecked(),
             }
         }
     }
 
+    /// Replaces the type name stored in a `TAG_STATIC_TYPE_ONLY` or `TAG_STATIC_TYPED_ORIGINAL`
+    /// value, keeping any location stored in the upper bits.
+    fn with_type_name(mut self, name: &'static str) -> Self {
+        if self.tag() == TAG_STATIC_TYPED_ORIGINAL {
+            let ptr = &TypedCodeInfo::get(name, self.typed_info().source).info;
+            assert!(fits_pointer_mask(ptr as *const _ as usize));
+            let kept_bits = self.tag.get() & (!POINTER_MASK | TAG_BITS_MASK);
+            unsafe {
+                self.tag = NonZeroUsize::new_unchecked((ptr as *const _ as usize) | kept_bits);
+            }
+            return self;
+        }
+        assert_eq!(self.tag(), TAG_STATIC_TYPE_ONLY);
+        let mut info = PackedOriginInfo::for_origin(ErrorOrigin::TypeOrigin(name, None));
+        unsafe {
+            info.tag =
+                NonZeroUsize::new_unchecked(info.tag.get() | (self.tag.get() & !POINTER_MASK));
+        }
+        info.additional |= self.additional & !POINTER_MASK;
+        info
+    }
+
+    /// Replaces the outermost context of this value with another with the same error code,
+    /// keeping all flags.
+    ///
+    /// Returns `None` if the outermost frame is not a context that can be replaced, as this value
+    /// only stores a type name, or the outermost frame is the origin of a conversion.
+    fn with_outermost_context(
+        mut self,
+        f: impl FnOnce(&'static ErrorInfoImpl) -> &'static ErrorInfoImpl,
+    ) -> Option<Self> {
+        if !self.has_contexts() {
+            return None;
+        }
+        if let Some(second) = self.context_second() {
+            let new = f(second);
+            assert!(fits_pointer_mask(new as *const _ as usize));
+            debug_assert_eq!(new.error_code.is_some(), second.error_code.is_some());
+            let kept_bits = self.additional & (!POINTER_MASK | FLAG_BITS_MASK);
+            self.additional = new as *const _ as usize | kept_bits;
+        } else if self.tag() == TAG_STATIC_TYPED_ORIGINAL {
+            return None;
+        } else {
+            let new = f(self.context_first());
+            assert!(fits_pointer_mask(new as *const _ as usize));
+            let kept_bits = self.tag.get() & (!POINTER_MASK | TAG_BITS_MASK);
+            unsafe {
+                self.tag = NonZeroUsize::new_unchecked(new as *const _ as usize | kept_bits);
+            }
+        }
+        Some(self)
+    }
+
+    /// Marks the error code of this value as frozen, so later contexts with an error code are
+    /// dropped rather than stored.
+    ///
+    /// Values that only store a type name are left unchanged, as they have no room for the flag.
+    fn with_frozen_code(mut self) -> Self {
+        if self.tag() != TAG_STATIC_TYPE_ONLY {
+            self.additional |= FROZEN_BIT_MASK;
+        }
+        self
+    }
+
+    /// Returns whether the second context has an error code, without loading it.
+    fn is_second_coded(&self) -> bool {
+        self.tag() != TAG_STATIC_TYPE_ONLY && self.tag.get() & SECOND_CODED_BIT_MASK != 0
+    }
+
+    fn set_second_coded(&mut self, coded: bool) {
+        let tag = self.tag.get() & !SECOND_CODED_BIT_MASK;
+        let tag = if coded { tag | SECOND_CODED_BIT_MASK } else { tag };
+        self.tag = unsafe { NonZeroUsize::new_unchecked(tag) };
+    }
+
+    fn is_code_frozen(&self) -> bool {
+        self.tag() != TAG_STATIC_TYPE_ONLY && self.additional & FROZEN_BIT_MASK != 0
+    }
+
+    /// Marks the error code of this value as taken, so no code is returned until a context with
+    /// an error code is added. This also unfreezes the error code.
+    ///
+    /// Values that only store a type name are left unchanged, as they have no error code.
+    fn with_taken_code(mut self) -> Self {
+        if self.tag() != TAG_STATIC_TYPE_ONLY {
+            self.additional = (self.additional & !FROZEN_BIT_MASK) | TAKEN_BIT_MASK;
+        }
+        self
+    }
+
+    fn is_code_taken(&self) -> bool {
+        self.tag() != TAG_STATIC_TYPE_ONLY && self.additional & TAKEN_BIT_MASK != 0
+    }
+
+    /// Pushes the contexts stored in this value onto the value of another error, marking the
+    /// frames in between as omitted.
+    ///
+    /// Returns `None` if this value only stores a type name, as it cannot be stored as context.
+    fn graft_onto(self, mut cause: PackedOriginInfo) -> Option<PackedOriginInfo> {
+        if self.tag() == TAG_STATIC_TYPE_ONLY {
+            return None;
+        }
+        if cause.tag() != TAG_STATIC_TYPE_ONLY {
+            cause.additional &= !FROZEN_BIT_MASK;
+        }
+        let mut info = cause.with_context(self.context_first());
+        if let Some(second) = self.context_second() {
+            info = info.with_context(second);
+        }
+        if info.context_second().is_some() {
+            info.additional |= OMITTED_BIT_MASK;
+        }
+        if self.is_code_taken() {
+            info = info.with_taken_code();
+        }
+        if self.is_code_frozen() {
+            info = info.with_frozen_code();
+        }
+        Some(info)
+    }
+
+    /// Returns the name of the type this value was converted from, if it is stored.
+    fn type_name(&self) -> Option<&'static str> {
+        match self.tag() {
+            TAG_STATIC_TYPE_ONLY => Some(self.ty_name()),
+            TAG_STATIC_TYPED_ORIGINAL => Some(self.typed_info().type_name),
+            _ => None,
+        }
+    }
+
+    fn typed_info(&self) -> &'static TypedCodeInfo {
+        assert_eq!(self.tag(), TAG_STATIC_TYPED_ORIGINAL);
+        // SAFETY: the first context of a `TAG_STATIC_TYPED_ORIGINAL` value always points to the
+        // info of a `TypedCodeInfo`, which is its first field
+        unsafe { &*(self.context_first() as *const ErrorInfoImpl as *const TypedCodeInfo) }
+    }
+
     fn ty_name(&self) -> &'static str {
         unsafe {
             assert_eq!(self.tag(), TAG_STATIC_TYPE_ONLY);
-            let ptr = self.additional as *const u8;
-            let len = self.tag.get() >> 2;
+            let ptr = (self.additional & POINTER_MASK) as *const u8;
+            let len = unpack_type_len(self.tag.get(), POINTER_MASK);
+            debug_assert!(!ptr.is_null(), "type name pointer is null");
             let slice = core::slice::from_raw_parts(ptr, len);
             core::str::from_utf8_unchecked(slice)
         }
@@ -146,49 +652,95 @@ impl PackedOriginInfo {
 
     fn context_first(&self) -> &'static ErrorInfoImpl {
         unsafe {
-            assert!(self.tag() == TAG_STATIC_ORIGINAL || self.tag() == TAG_STATIC_CONTEXT_ONLY);
-            &*((self.tag.get() & !TAG_MASK) as *const ErrorInfoImpl)
+            assert!(self.has_contexts());
+            let ptr = self.tag.get() & POINTER_MASK & !TAG_BITS_MASK;
+            debug_check_info_ptr(ptr);
+            &*(ptr as *const ErrorInfoImpl)
         }
     }
 
     fn context_second(&self) -> Option<&'static ErrorInfoImpl> {
         unsafe {
-            assert!(self.tag() == TAG_STATIC_ORIGINAL || self.tag() == TAG_STATIC_CONTEXT_ONLY);
-            if (self.additional & !OMITTED_BIT_MASK) == 0 {
+            assert!(self.has_contexts());
+            let ptr = self.additional & POINTER_MASK & !FLAG_BITS_MASK;
+            if ptr == 0 {
                 None
             } else {
-                Some(&*((self.additional & !OMITTED_BIT_MASK) as *const ErrorInfoImpl))
+                debug_check_info_ptr(ptr);
+                Some(&*(ptr as *const ErrorInfoImpl))
             }
         }
     }
 
     fn has_omitted_context(self) -> bool {
-        if self.tag() == TAG_STATIC_CONTEXT_ONLY || self.tag() == TAG_STATIC_ORIGINAL {
+        if self.has_contexts() {
             self.additional & OMITTED_BIT_MASK == OMITTED_BIT_MASK
         } else {
             false
         }
     }
 
+    fn root_code(&self) -> Option<&'static ErrorCodeInfo> {
+        if self.tag() == TAG_STATIC_TYPE_ONLY {
+            None
+        } else {
+            self.context_first()
+                .error_code
+                .or_else(|| self.context_second().and_then(|x| x.error_code))
+        }
+    }
+
     #[inline(never)]
     fn code(&self) -> Option<&'static ErrorCodeInfo> {
-        if self.tag() == TAG_STATIC_TYPE_ONLY {
+        if self.tag() == TAG_STATIC_TYPE_ONLY || self.is_code_taken() {
             None
         } else {
-            if let Some(context_second) = self.context_second() {
-                if context_second.error_code.is_some() {
-                    return context_second.error_code;
-                }
+            if let Some(context_second) = self.context_second()
+                && context_second.error_code.is_some()
+            {
+                return context_second.error_code;
             }
             self.context_first().error_code
         }
     }
 }
 
+/// The info of a conversion from a foreign type that was mapped to an error code, which also
+/// stores the name of the type.
+///
+/// The unboxed representations have no room for both a type name and an error code, so one of
+/// these is leaked for each distinct pair, and the packed value points to its `info`.
+#[repr(C)]
+struct TypedCodeInfo {
+    info: ErrorInfoImpl,
+    type_name: &'static str,
+    /// The info this was created from.
+    source: &'static ErrorInfoImpl,
+}
+impl TypedCodeInfo {
+    /// Returns the info for a given type name and error code info, creating it if needed.
+    fn get(type_name: &'static str, source: &'static ErrorInfoImpl) -> &'static TypedCodeInfo {
+        static TABLE: InternTable<(usize, usize, usize), TypedCodeInfo> = InternTable::new();
+        let key = (type_name.as_ptr() as usize, type_name.len(), source as *const _ as usize);
+        TABLE.get_or_insert_with(key, || TypedCodeInfo { info: *source, type_name, source })
+    }
+}
+
+/// Checks that a pointer unpacked from a [`PackedOriginInfo`] can point to an [`ErrorInfoImpl`].
+#[inline(always)]
+fn debug_check_info_ptr(ptr: usize) {
+    debug_assert!(ptr != 0, "`ErrorInfoImpl` pointer is null");
+    debug_assert!(
+        ptr.is_multiple_of(align_of::<ErrorInfoImpl>()),
+        "`ErrorInfoImpl` pointer is misaligned: {ptr:#x}"
+    );
+}
+
+#[derive(Clone)]
 pub struct ErrorImplIter {
     phase: ErrorIterPhase,
     origin_info: PackedOriginInfo,
-    original_location: Option<&'static Location<'static>>,
+    original_location: Option<DecodedLocation>,
 }
 #[derive(Copy, Clone, Eq, PartialEq)]
 enum ErrorIterPhase {
@@ -207,32 +759,34 @@ impl Iterator for ErrorImplIter {
         // returns the last context frame
         if self.phase == ErrorIterPhase::LastContext {
             self.phase = ErrorIterPhase::FirstContext;
-            if tag == TAG_STATIC_ORIGINAL || tag == TAG_STATIC_CONTEXT_ONLY {
-                if let Some(context_second) = self.origin_info.context_second() {
-                    return Some(ErrorFrameImpl {
-                        data: ErrorFrameData::decode_static(Some(context_second), None),
-                        location: context_second.location.map(|x| *x),
-                    });
-                }
+            if self.origin_info.has_contexts()
+                && let Some(context_second) = self.origin_info.context_second()
+            {
+                return Some(ErrorFrameImpl {
+                    data: ErrorFrameData::decode_static(Some(context_second), None),
+                    location: context_second.location.copied(),
+                });
             }
         }
 
         // returns the first context frame
         if self.phase == ErrorIterPhase::FirstContext {
             self.phase = ErrorIterPhase::LocationMismatchFrame;
-            if tag == TAG_STATIC_ORIGINAL || tag == TAG_STATIC_CONTEXT_ONLY {
+            if self.origin_info.has_contexts() {
                 let context_first = self.origin_info.context_first();
-                let location = if tag == TAG_STATIC_ORIGINAL {
+                let location = if self.origin_info.is_original() {
                     self.original_location
-                        .map(DecodedLocation::from)
-                        .or_else(|| context_first.location.map(|x| *x))
+                        .or_else(|| context_first.location.copied())
                 } else {
-                    context_first.location.map(|x| *x)
+                    context_first.location.copied()
                 };
-                return Some(ErrorFrameImpl {
-                    data: ErrorFrameData::decode_static(Some(context_first), None),
-                    location,
-                });
+                let data = if tag == TAG_STATIC_TYPED_ORIGINAL {
+                    let ty = self.origin_info.typed_info().type_name;
+                    ErrorFrameData::TypeFrame(ty, context_first.error_code)
+                } else {
+                    ErrorFrameData::decode_static(Some(context_first), None)
+                };
+                return Some(ErrorFrameImpl { data, location });
             }
         }
 
@@ -244,15 +798,14 @@ impl Iterator for ErrorImplIter {
                 let context_first = self.origin_info.context_first();
                 if let Some(location_a) = context_first.location
                     && let Some(location_b) = self.original_location
+                    && !location_a.is_near(location_b)
                 {
-                    if !location_a.is_same(location_b.into()) {
-                        return Some(ErrorFrameImpl {
-                            data: ErrorFrameData::InternalContext(
-                                InternalContextType::ErrorTypeConstructed,
-                            ),
-                            location: Some(*location_a),
-                        });
-                    }
+                    return Some(ErrorFrameImpl {
+                        data: ErrorFrameData::InternalContext(
+                            InternalContextType::ErrorTypeConstructed,
+                        ),
+                        location: Some(*location_a),
+                    });
                 }
             }
         }
@@ -265,13 +818,13 @@ impl Iterator for ErrorImplIter {
                 // we have a static type node!
                 return Some(ErrorFrameImpl {
                     data: ErrorFrameData::TypeFrame(self.origin_info.ty_name(), None),
-                    location: self.original_location.map(DecodedLocation::from),
+                    location: self.original_location,
                 });
             } else if tag == TAG_STATIC_CONTEXT_ONLY {
                 // we have a former type node that we appended context to
                 return Some(ErrorFrameImpl {
                     data: ErrorFrameData::InternalContext(InternalContextType::OriginalTypeLost),
-                    location: self.original_location.map(DecodedLocation::from),
+                    location: self.original_location,
                 });
             }
         }
@@ -279,24 +832,29 @@ impl Iterator for ErrorImplIter {
         // returns the frames omitted message, if needed
         if self.phase == ErrorIterPhase::FramesOmitted {
             self.phase = ErrorIterPhase::Ended;
-            if tag == TAG_STATIC_ORIGINAL || tag == TAG_STATIC_CONTEXT_ONLY {
-                if self.origin_info.has_omitted_context() {
-                    return Some(ErrorFrameImpl {
-                        data: ErrorFrameData::InternalContext(
-                            InternalContextType::FurtherFramesOmitted,
-                        ),
-                        location: None,
-                    });
-                }
+            if self.origin_info.has_contexts() && self.origin_info.has_omitted_context() {
+                return Some(ErrorFrameImpl {
+                    data: ErrorFrameData::InternalContext(
+                        InternalContextType::FurtherFramesOmitted,
+                    ),
+                    location: None,
+                });
             }
         }
 
         // we return none at this point!
         None
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // there are only a few phases, so it is cheap to simply run the iterator to the end
+        let len = self.clone().count();
+        (len, Some(len))
+    }
 }
+impl ExactSizeIterator for ErrorImplIter {}
 
 const _CHECK_REQUIRED_ALIGNMENT: () = {
-    let required_alignment = 4;
+    let required_alignment = 8;
     assert!(align_of::<ErrorInfoImpl>() >= required_alignment);
 };
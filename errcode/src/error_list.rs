@@ -0,0 +1,81 @@
+//! A collection of errors, for reporting every error found instead of only the first.
+
+use crate::Error;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+
+/// A collection of [`Error`]s, such as every problem found while validating an input.
+///
+/// Errors can be added with [`Errors::push`], [`Extend`] or [`FromIterator`], and then turned into
+/// a [`Result`] with [`Errors::into_result`] once all of them have been collected.
+#[derive(Clone, Debug, Default)]
+pub struct Errors {
+    errors: Vec<Error>,
+}
+impl Errors {
+    /// Creates a new empty collection.
+    pub fn new() -> Self {
+        Errors::default()
+    }
+
+    /// Adds an error to this collection.
+    pub fn push(&mut self, error: Error) {
+        self.errors.push(error);
+    }
+
+    /// Returns the number of errors in this collection.
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Returns whether this collection contains no errors.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns an iterator over the errors in this collection.
+    pub fn iter(&self) -> core::slice::Iter<'_, Error> {
+        self.errors.iter()
+    }
+
+    /// Returns `Ok(ok)` if this collection is empty, and the collection itself otherwise.
+    pub fn into_result<T>(self, ok: T) -> Result<T, Errors> {
+        if self.errors.is_empty() { Ok(ok) } else { Err(self) }
+    }
+}
+impl Extend<Error> for Errors {
+    fn extend<I: IntoIterator<Item = Error>>(&mut self, iter: I) {
+        self.errors.extend(iter);
+    }
+}
+impl FromIterator<Error> for Errors {
+    fn from_iter<I: IntoIterator<Item = Error>>(iter: I) -> Self {
+        Errors { errors: iter.into_iter().collect() }
+    }
+}
+impl IntoIterator for Errors {
+    type Item = Error;
+    type IntoIter = alloc::vec::IntoIter<Error>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
+}
+impl<'a> IntoIterator for &'a Errors {
+    type Item = &'a Error;
+    type IntoIter = core::slice::Iter<'a, Error>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.iter()
+    }
+}
+impl Display for Errors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for (i, error) in self.errors.iter().enumerate() {
+            if i != 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{}. {error}", i + 1)?;
+        }
+        Ok(())
+    }
+}
+impl core::error::Error for Errors {}
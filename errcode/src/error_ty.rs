@@ -1,50 +1,100 @@
-use crate::error_code::ErrorCode;
+use crate::error_code::{CodeError, ErrorCode, ErrorCodeInfo, Severity};
 use crate::error_impl::{
-    ErrorFrameImpl, ErrorImpl, ErrorImplFunctions, ErrorInfoImpl, ErrorOrigin,
+    DecodedLocation, ErrorFrameImpl, ErrorImpl, ErrorImplFunctions, ErrorInfoImpl, ErrorOrigin,
+    StaticMessageInfo,
 };
+use crate::intern::InternTable;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use core::any::{TypeId, type_name};
-use core::fmt::{Arguments, Debug, Display, Formatter};
+use core::fmt::{Arguments, Debug, Display, Formatter, Write};
 
 #[derive(Clone)]
 #[repr(transparent)]
 pub struct Error {
-    underlying: ErrorImpl,
+    pub(crate) underlying: ErrorImpl,
 }
 impl Error {
     #[inline(never)]
     #[track_caller]
     pub fn from_info(info: ErrorInfo) -> Self {
-        Error {
-            underlying: ErrorImpl::new(
-                ErrorOrigin::StaticOrigin(info.info),
-                info.arguments.as_ref(),
-            ),
-        }
+        Error::new(ErrorImpl::new(ErrorOrigin::StaticOrigin(info.info), info.arguments.as_ref()))
     }
 
     #[inline(never)]
     #[track_caller]
     pub fn from_code<T: ErrorCode>(code: T) -> Self {
-        Error {
-            underlying: ErrorImpl::new(ErrorOrigin::StaticOrigin(T::error_source(code)), None),
-        }
+        Error::new(ErrorImpl::new(ErrorOrigin::StaticOrigin(T::error_source(code)), None))
     }
 
     #[inline(never)]
     #[track_caller]
     pub fn from_type(name: &'static str) -> Self {
-        Error { underlying: ErrorImpl::new(ErrorOrigin::TypeOrigin(name, None), None) }
+        Error::new(ErrorImpl::new(ErrorOrigin::TypeOrigin(name, None), None))
     }
 
     #[inline(never)]
     #[track_caller]
     pub fn from_type_with_code<T: ErrorCode>(name: &'static str, code: T) -> Self {
-        Error {
-            underlying: ErrorImpl::new(
-                ErrorOrigin::TypeOrigin(name, Some(T::error_source(code))),
-                None,
-            ),
-        }
+        Error::new(ErrorImpl::new(ErrorOrigin::TypeOrigin(name, Some(T::error_source(code))), None))
+    }
+
+    /// Creates an error from a message that has already been formatted with `format_args!`.
+    ///
+    /// The message is only stored with the `repr_full` feature. The other representations
+    /// cannot store messages chosen at runtime, and show the message as unformatted instead, like
+    /// they show an [`error_info!`](crate::error_info) with arguments.
+    #[inline(never)]
+    #[track_caller]
+    pub fn from_args(args: Arguments<'_>) -> Error {
+        /// The info of errors created from arguments, with a placeholder for the message.
+        static ARGS_INFO: ErrorInfoImpl =
+            ErrorInfoImpl::new(None, StaticMessageInfo::Unformatted("{}"), None);
+
+        Error::new(ErrorImpl::new(ErrorOrigin::StaticOrigin(&ARGS_INFO), Some(&args)))
+    }
+
+    /// Creates an error from a boxed error of an unknown type, with `msg` as its outermost
+    /// context.
+    ///
+    /// This is the same as converting an error of a concrete type, except that no type name is
+    /// stored for the origin frame. The boxed error itself is not kept.
+    ///
+    /// As `msg` is stored as a static message, it is shown by every representation. An error info
+    /// is leaked the first time each `msg` is used, so it should be a string literal.
+    #[inline(never)]
+    #[track_caller]
+    pub fn wrap_dyn(source: Box<dyn core::error::Error + Send + Sync>, msg: &'static str) -> Error {
+        let code = error_code_for_error(&*source);
+        let mut underlying = ErrorImpl::new(
+            ErrorOrigin::TypeOrigin("dyn core::error::Error", code),
+            Some(&format_args!("{source}")),
+        );
+        underlying.push_sources(&*source);
+        underlying.push_context(crate::intern::message_info(None, msg, None), None);
+        Error::new(underlying)
+    }
+
+    /// Creates an error directly from an origin. Used by the fuzzing entry points.
+    #[inline(never)]
+    #[track_caller]
+    pub(crate) fn from_origin(origin: ErrorOrigin) -> Self {
+        Error::new(ErrorImpl::new(origin, None))
+    }
+
+    /// Wraps a newly created error representation, and runs the error hook on it.
+    #[inline(always)]
+    fn new(underlying: ErrorImpl) -> Self {
+        let error = Error { underlying };
+        crate::hook::run_error_hook(&error);
+        error
+    }
+
+    /// Panics if the internal invariants of the underlying representation do not hold.
+    pub(crate) fn assert_invariants(&self) {
+        self.underlying.assert_invariants();
     }
 
     /// Returns whether this error has an error code.
@@ -53,6 +103,324 @@ impl Error {
         self.underlying.code().is_some()
     }
 
+    /// Returns the current error code of this error, if any.
+    ///
+    /// Each error code has a single [`ErrorCodeInfo`], so the same code always returns the same
+    /// reference, and references that are equal by address are always the same code. See
+    /// [`Error::code_ptr_eq`]. This also holds for codes added with [`Error::attach_raw_code`].
+    #[inline(always)]
+    pub fn code(&self) -> Option<&'static ErrorCodeInfo> {
+        self.underlying.code()
+    }
+
+    /// Returns the value of the current error code, such as to send in a protocol response.
+    ///
+    /// Like [`ErrorCodeInfo::value`], values that do not fit in 32 bits are truncated, and
+    /// negative values are returned in two's complement. Use [`ErrorCodeInfo::value_i64`] or
+    /// [`ErrorCodeInfo::value_u64`] for the full value.
+    #[inline(always)]
+    pub fn code_value(&self) -> Option<u32> {
+        self.underlying.code().map(|x| x.value)
+    }
+
+    /// Returns the error code of the deepest frame that has one.
+    ///
+    /// Unlike [`Error::code`], this ignores codes added by later context, and classifies the
+    /// error by its fundamental cause instead. The unboxed representations only store the first
+    /// context of the error, so this may not be the deepest code if frames were omitted.
+    #[inline(always)]
+    pub fn root_code(&self) -> Option<&'static ErrorCodeInfo> {
+        self.underlying.root_code()
+    }
+
+    /// Returns the error codes of the frames of this error, starting from the origin, such as to
+    /// report how the classification of an error changed as context was added.
+    ///
+    /// The unboxed representations only store some of the frames of an error, so codes in frames
+    /// that were omitted are not included.
+    pub fn depth_first_codes(&self) -> impl Iterator<Item = &'static ErrorCodeInfo> + '_ {
+        let codes: Vec<_> = self.underlying.iter().filter_map(|x| x.code()).collect();
+        codes.into_iter().rev()
+    }
+
+    /// Returns whether this error originated from the conversion of a foreign error type,
+    /// including conversions that map to an error code, such as from `std::io::Error`.
+    #[inline(always)]
+    pub fn is_conversion(&self) -> bool {
+        self.underlying.is_conversion()
+    }
+
+    /// Returns the name of the foreign type this error was converted from, such as
+    /// `std::io::Error`, or `None` if it was not converted from a foreign type.
+    ///
+    /// The unboxed representations do not store the type name once context is added to a
+    /// conversion that did not map to an error code, and return `None` for those errors as well.
+    pub fn origin_type_name(&self) -> Option<&'static str> {
+        self.underlying.origin_type_name()
+    }
+
+    /// Returns whether this error or any of its causes was converted from a foreign type with the
+    /// given path, such as `std::io::Error`.
+    ///
+    /// The recorded name is the [`type_name`] of the foreign type, which is the path the type is
+    /// defined at, such as `std::io::error::Error`. To match re-exports, the segments of the given
+    /// path only need to appear in the recorded name in order, with the same last segment, and
+    /// `std`, `core` and `alloc` are treated as the same crate. A single segment such as `Error`
+    /// only matches the last segment. Generic arguments are ignored.
+    ///
+    /// This allows handling wrapped foreign errors without depending on the crate that defines
+    /// them. The unboxed representations only check the type name returned by
+    /// [`Error::origin_type_name`].
+    pub fn caused_by_type(&self, path: &str) -> bool {
+        self.origin_type_name()
+            .is_some_and(|x| type_path_matches(x, path))
+            || self
+                .underlying
+                .iter()
+                .filter_map(|x| x.type_name())
+                .any(|x| type_path_matches(x, path))
+    }
+
+    /// Returns the URL documenting the current error code, if there is one.
+    ///
+    /// See [`ErrorCodeInfo::doc_url`] for details.
+    pub fn doc_url(&self) -> Option<String> {
+        self.underlying.code().and_then(ErrorCodeInfo::doc_url)
+    }
+
+    /// Returns the group of the current error code, if there is one.
+    ///
+    /// This is set with the `#[code(group = "...")]` attribute, on either the enum or a variant.
+    pub fn code_group(&self) -> Option<&'static str> {
+        self.underlying.code().and_then(|x| x.group)
+    }
+
+    /// Returns the severity of the current error code, if it has one.
+    ///
+    /// This is set with the `#[code(severity = "...")]` attribute, on either the enum or a
+    /// variant.
+    pub fn severity(&self) -> Option<Severity> {
+        self.underlying.code().and_then(|x| x.severity)
+    }
+
+    /// Returns the severity of the current error code, or `default` if it has none.
+    pub fn severity_or(&self, default: Severity) -> Severity {
+        self.severity().unwrap_or(default)
+    }
+
+    /// Returns the highest severity of the error codes of any frame of this error.
+    ///
+    /// Unlike [`Error::severity`], this also finds severe causes that were given a less severe
+    /// error code by an outer context.
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.underlying
+            .iter()
+            .filter_map(|x| x.code()?.severity)
+            .max()
+    }
+
+    /// Returns whether the current error code has [`Severity::Fatal`].
+    pub fn is_fatal(&self) -> bool {
+        self.severity() == Some(Severity::Fatal)
+    }
+
+    /// Returns whether the current error code has [`Severity::Warning`].
+    pub fn is_warning(&self) -> bool {
+        self.severity() == Some(Severity::Warning)
+    }
+
+    /// Returns the number of frames in this error.
+    #[inline(always)]
+    pub fn frame_count(&self) -> usize {
+        self.underlying.iter().len()
+    }
+
+    /// Returns an iterator over the frames of this error, starting from the outermost context.
+    ///
+    /// Every representation yields frames in this order, ending with the frame the error was
+    /// created with. Representations may differ in which frames are stored, however, and may add
+    /// internal frames such as a note that frames were omitted.
+    #[inline(always)]
+    pub fn frames(&self) -> ErrorFrameIter<'_> {
+        ErrorFrameIter { iter: self.underlying.iter() }
+    }
+
+    /// Returns a copy of this error, such as to both log and return it.
+    ///
+    /// With `repr_full` and the `shared_clone` feature, the copy shares its frames with this error
+    /// until either is modified, so this takes constant time. Without `shared_clone`, `repr_full`
+    /// copies the frames. The unboxed representations are always cheap to copy. In every case,
+    /// this is the same as [`Clone::clone`].
+    #[inline(always)]
+    pub fn clone_shallow(&self) -> Error {
+        self.clone()
+    }
+
+    /// Returns whether any frame of this error matches a predicate.
+    pub fn any_frame<F: Fn(&ErrorFrame) -> bool>(&self, pred: F) -> bool {
+        self.frames().any(|x| pred(&x))
+    }
+
+    /// Returns the first frame of this error matching a predicate, starting from the outermost
+    /// context.
+    pub fn find_frame<F: Fn(&ErrorFrame) -> bool>(&self, pred: F) -> Option<ErrorFrame> {
+        self.frames().find(|x| pred(x))
+    }
+
+    /// Returns the outermost frame of this error that has an error code, such as to show the code
+    /// together with the message it was added with.
+    ///
+    /// This is the frame the current code comes from, unless the code was changed with
+    /// [`Error::freeze_code`] or [`Error::take_code`].
+    pub fn first_with_code(&self) -> Option<ErrorFrame> {
+        self.find_frame(|x| x.code().is_some())
+    }
+
+    /// Returns an iterator over the frames of this error paired with their position, for reporters
+    /// that need to know where a frame is in the trace while rendering it.
+    pub fn frames_meta(&self) -> impl ExactSizeIterator<Item = (ErrorFrame, FrameMeta)> + '_ {
+        let last_real = self
+            .underlying
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| !x.is_internal())
+            .map(|(i, _)| i)
+            .fold(None, |_, i| Some(i));
+        self.frames().enumerate().map(move |(depth, frame)| {
+            let meta =
+                FrameMeta { is_first: depth == 0, is_last_real: Some(depth) == last_real, depth };
+            (frame, meta)
+        })
+    }
+
+    /// Returns an owned snapshot of the frames of this error, starting from the outermost context.
+    ///
+    /// Unlike [`Error::frames`], the returned frames do not borrow the error.
+    pub fn frames_owned(&self) -> Vec<OwnedFrame> {
+        self.underlying
+            .iter()
+            .map(|x| OwnedFrame {
+                message: x.message().map(String::from),
+                location: x.location(),
+                code: x.code(),
+            })
+            .collect()
+    }
+
+    /// Consumes this error, returning its current error code and the message and location of
+    /// each frame, starting from the outermost context.
+    ///
+    /// This is intended for building custom serialization formats.
+    #[allow(clippy::type_complexity)]
+    pub fn into_parts(
+        self,
+    ) -> (Option<&'static ErrorCodeInfo>, Vec<(Option<String>, Option<DecodedLocation>)>) {
+        let frames = self
+            .underlying
+            .iter()
+            .map(|x| (x.message().map(String::from), x.location()))
+            .collect();
+        (self.underlying.code(), frames)
+    }
+
+    /// Returns whether this error has the same current error code as another error.
+    ///
+    /// Errors without an error code never compare equal.
+    pub fn same_code_as(&self, other: &Error) -> bool {
+        match (self.underlying.code(), other.underlying.code()) {
+            (Some(a), Some(b)) => a.same_code(b),
+            _ => false,
+        }
+    }
+
+    /// Returns whether this error has the same current error code as another error, comparing
+    /// the code infos by address.
+    ///
+    /// This is faster than [`Error::same_code_as`], and gives the same result except for codes
+    /// added with [`Error::attach_raw_code`]. Errors without an error code never compare equal.
+    pub fn code_ptr_eq(&self, other: &Error) -> bool {
+        match (self.underlying.code(), other.underlying.code()) {
+            (Some(a), Some(b)) => core::ptr::eq(a, b),
+            _ => false,
+        }
+    }
+
+    /// Returns whether the current error code of this error is any of the given codes.
+    ///
+    /// This allows checking against a set of codes chosen at runtime, such as from configuration.
+    pub fn code_matches_any(&self, codes: &[&'static ErrorCodeInfo]) -> bool {
+        match self.underlying.code() {
+            Some(code) => codes.iter().any(|x| x.same_code(code)),
+            None => false,
+        }
+    }
+
+    /// Returns whether this error was created at the same line and with the same code as another
+    /// error, ignoring any context added to either error and the source chain of a converted
+    /// error.
+    pub fn is_same_origin(&self, other: &Error) -> bool {
+        let (Some(a), Some(b)) = (self.underlying.origin_frame(), other.underlying.origin_frame())
+        else {
+            return false;
+        };
+        let same_location = match (a.location(), b.location()) {
+            (Some(a), Some(b)) => a.is_same(b),
+            (None, None) => true,
+            _ => false,
+        };
+        let same_code = match (a.code(), b.code()) {
+            (Some(a), Some(b)) => a.same_code(b),
+            (None, None) => true,
+            _ => false,
+        };
+        same_location && same_code
+    }
+
+    /// Returns a key suitable for grouping similar errors together.
+    ///
+    /// This is a hash of the error codes of every frame, and the message of the outermost frame.
+    /// Locations are not included, and the key is stable across runs of the same program.
+    pub fn grouping_key(&self) -> u64 {
+        let mut hasher = Fnv1aHasher::new();
+        for (idx, frame) in self.underlying.iter().enumerate() {
+            if idx == 0 {
+                if let Some(message) = frame.message() {
+                    hasher.write(message.as_bytes());
+                }
+                hasher.write(&[0xFF]);
+            }
+            if let Some(code) = frame.code() {
+                hasher.write(code.type_name.as_bytes());
+                hasher.write(b"::");
+                hasher.write(code.variant_name.as_bytes());
+                hasher.write(&[0]);
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Returns a fingerprint identifying the structure of this error, for deduplicating errors
+    /// in error tracking systems.
+    ///
+    /// This is a hash of the error codes of every frame, in order, and of the foreign type the
+    /// error was converted from, if known. Unlike [`Error::grouping_key`], no messages are
+    /// included, so errors that only differ in their messages or locations have the same
+    /// fingerprint. The fingerprint is stable across runs of the same program.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = Fnv1aHasher::new();
+        for code in self.underlying.iter().filter_map(|x| x.code()) {
+            hasher.write(code.type_name.as_bytes());
+            hasher.write(b"::");
+            hasher.write(code.variant_name.as_bytes());
+            hasher.write(&[0]);
+        }
+        if let Some(name) = self.origin_type_name() {
+            hasher.write(&[0xFF]);
+            hasher.write(name.as_bytes());
+        }
+        hasher.finish()
+    }
+
     /// Returns whether this error has a given error code.
     #[inline(always)]
     pub fn is<T: ErrorCode>(&self, value: T) -> bool {
@@ -63,6 +431,35 @@ impl Error {
         }
     }
 
+    /// Returns whether any frame of this error has a given error code, rather than only the
+    /// current one.
+    ///
+    /// The unboxed representations only store some of the frames of an error, so codes in frames
+    /// that were omitted are not found.
+    pub fn caused_by_code<T: ErrorCode>(&self, value: T) -> bool {
+        let Some(target) = T::error_source(value).error_code else {
+            return false;
+        };
+        self.underlying
+            .iter()
+            .filter_map(|x| x.code())
+            .any(|x| x.same_code(target))
+    }
+
+    /// Returns whether either the current error code or the code of any frame of this error is a
+    /// given error code.
+    ///
+    /// This combines [`Error::is`] and [`Error::caused_by_code`]. The current code is checked
+    /// separately, as it is not always stored in a frame by every representation.
+    pub fn is_code_or_cause<T: ErrorCode>(&self, value: T) -> bool {
+        let Some(target) = T::error_source(value).error_code else {
+            return false;
+        };
+        let matches = |x: &ErrorCodeInfo| x.same_code(target);
+        self.underlying.code().is_some_and(matches)
+            || self.underlying.iter().filter_map(|x| x.code()).any(matches)
+    }
+
     /// Returns whether this error has an error code of the given type.
     #[inline(always)]
     pub fn is_type<T: ErrorCode>(&self) -> bool {
@@ -77,25 +474,392 @@ impl Error {
     #[inline(always)]
     pub fn downcast_code<T: ErrorCode>(&self) -> Option<T> {
         if let Some(code) = self.underlying.code() {
-            if code.tid == TypeId::of::<T>() {
-                Some(T::from_value(code.value))
-            } else {
-                None
-            }
+            code.decode_value()
         } else {
             None
         }
     }
 
+    /// Downcasts the error code to a given type, returning why it could not be downcast on
+    /// failure.
+    ///
+    /// Type names are reported as the name of the enum without its module path, as in
+    /// [`ErrorCodeInfo::type_name`].
+    pub fn as_code_enum<T: ErrorCode>(&self) -> Result<T, CodeError> {
+        let code = self.underlying.code().ok_or(CodeError::NoCode)?;
+        if let Some(value) = code.decode_value() {
+            Ok(value)
+        } else {
+            let expected = type_name::<T>();
+            Err(CodeError::WrongType {
+                expected: expected.rsplit("::").next().unwrap_or(expected),
+                actual: code.type_name,
+            })
+        }
+    }
+
+    /// Downcasts the error code to a given type, returning a default if the error has no code of
+    /// that type.
+    #[inline(always)]
+    pub fn code_or<T: ErrorCode>(&self, default: T) -> T {
+        self.downcast_code::<T>().unwrap_or(default)
+    }
+
+    /// Returns the location where this error was originally created.
+    ///
+    /// Depending on the representation in use, this may be the location of the `error_info!`
+    /// invocation rather than the location the [`Error`] itself was constructed at, or may not
+    /// be available at all.
+    #[inline(always)]
+    pub fn source_location(&self) -> Option<DecodedLocation> {
+        self.underlying.source_location()
+    }
+
+    /// Returns an iterator over the locations of the frames of this error, in the order of
+    /// [`Error::frames`], skipping frames without one.
+    ///
+    /// With `repr_unboxed`, only frames created with [`error_info!`](crate::error_info) have a
+    /// location.
+    pub fn location_chain(&self) -> impl Iterator<Item = DecodedLocation> + '_ {
+        self.underlying.iter().filter_map(|x| x.location())
+    }
+
+    /// Writes the frames of this error using a given [`TraceStyle`].
+    pub fn format_with<W: Write + ?Sized>(
+        &self,
+        w: &mut W,
+        style: TraceStyle,
+    ) -> core::fmt::Result {
+        let mut iter = self.underlying.iter();
+        match style {
+            TraceStyle::OneLine => {
+                if let Some(frame) = iter.next() {
+                    write!(w, "{frame}")?;
+                }
+                for frame in iter {
+                    write!(w, ": {frame}")?;
+                }
+            }
+            TraceStyle::MultiLine => {
+                if let Some(frame) = iter.next() {
+                    write!(w, "{frame}")?;
+                }
+                for frame in iter {
+                    write!(w, "\n    caused by: {frame}")?;
+                }
+            }
+            TraceStyle::Tree => {
+                if let Some(frame) = iter.next() {
+                    write!(w, "{frame}")?;
+                }
+                for (depth, frame) in iter.enumerate() {
+                    w.write_char('\n')?;
+                    for _ in 0..depth {
+                        w.write_str("   ")?;
+                    }
+                    write!(w, "└─ {frame}")?;
+                }
+            }
+            TraceStyle::CodesOnly => {
+                let mut first = true;
+                for code in iter.filter_map(|x| x.code()) {
+                    if !first {
+                        w.write_str(", ")?;
+                    }
+                    write!(w, "{}::{}", code.type_name, code.variant_name)?;
+                    first = false;
+                }
+            }
+            TraceStyle::Debug => {
+                w.write_char('[')?;
+                let mut first = true;
+                let mut entry = |w: &mut W, args: Arguments<'_>| {
+                    if !first {
+                        w.write_str(", ")?;
+                    }
+                    first = false;
+                    w.write_fmt(args)
+                };
+                for frame in iter {
+                    entry(w, format_args!("{frame:?}"))?;
+                }
+                for attachment in self.attachments() {
+                    entry(w, format_args!("attachment: {attachment}"))?;
+                }
+                for note in self.notes() {
+                    entry(w, format_args!("= note: {note}"))?;
+                }
+                w.write_char(']')?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes this error as a single line suitable for a syslog message, using
+    /// [`TraceStyle::OneLine`].
+    pub fn write_syslog<W: Write + ?Sized>(&self, w: &mut W) -> core::fmt::Result {
+        self.format_with(w, TraceStyle::OneLine)
+    }
+
+    /// Returns a value that displays only the error codes of this error, using
+    /// [`TraceStyle::CodesOnly`].
+    pub fn codes_only(&self) -> impl Display + '_ {
+        DisplayStyle { error: self, style: TraceStyle::CodesOnly }
+    }
+
+    /// Writes the frame at a given index, in the order of [`Error::frames`], returning whether
+    /// the index was in range.
+    ///
+    /// This formats the frame the same way as [`Display`], without collecting the other frames.
+    pub fn fmt_frame_at<W: Write>(&self, idx: usize, w: &mut W) -> Result<bool, core::fmt::Error> {
+        match self.underlying.iter().nth(idx) {
+            Some(frame) => {
+                write!(w, "{frame}")?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Applies a function to the message of each frame of this error, such as to translate
+    /// messages at an API boundary.
+    ///
+    /// Error codes and locations are preserved. Messages are only stored with the `repr_full`
+    /// feature, and the other representations return the error unchanged.
+    pub fn map_frames<F: Fn(&str) -> String>(mut self, f: F) -> Self {
+        self.underlying.map_messages(f);
+        self
+    }
+
+    /// Applies a function to the location of each frame of this error, such as to strip build
+    /// paths before an error crosses a serialization boundary.
+    ///
+    /// As the file of a [`DecodedLocation`] is a `&'static str`, the function must return a
+    /// `'static` file as well, such as a suffix of the original file or one of a fixed set of
+    /// names. Locations are only rewritten with the `repr_full` feature, and the other
+    /// representations return the error unchanged.
+    pub fn rewrite_locations<F: Fn(DecodedLocation) -> DecodedLocation>(mut self, f: F) -> Self {
+        self.underlying.rewrite_locations(f);
+        self
+    }
+
+    /// Replaces the message of the outermost frame of this error, such as to turn an internal
+    /// message into one suitable for users, while keeping the rest of the frames. Returns whether
+    /// the message was replaced.
+    ///
+    /// The unboxed representations can only replace the message with a `&'static str`, and leave
+    /// the error unchanged for messages created at runtime. They also cannot replace the message
+    /// of the type name or code that an error was converted from. They leak an error info the
+    /// first time each static message replaces the message of a given frame, so the message
+    /// should be a string literal.
+    pub fn replace_message(&mut self, message: impl Into<Cow<'static, str>>) -> bool {
+        self.underlying.replace_message(message.into())
+    }
+
+    /// Returns a value that displays the frames of this error joined by a given separator.
+    ///
+    /// Frames added internally by the representation, such as notes that frames were omitted, are
+    /// skipped.
+    pub fn display_chain<'a>(&'a self, sep: &'a str) -> impl Display + 'a {
+        self.display_opts(DisplayOpts {
+            separator: Some(sep),
+            skip_internal: true,
+            ..DisplayOpts::default()
+        })
+    }
+
+    /// Returns a value that displays the frames of this error joined by a given separator,
+    /// starting from the root cause.
+    ///
+    /// Like [`Error::display_chain`], frames added internally by the representation are skipped.
+    pub fn display_rev<'a>(&'a self, sep: &'a str) -> impl Display + 'a {
+        self.display_opts(DisplayOpts {
+            separator: Some(sep),
+            skip_internal: true,
+            reverse: true,
+            ..DisplayOpts::default()
+        })
+    }
+
+    /// Returns a value that displays this error like [`Display`], but with every line after the
+    /// first indented by a given number of spaces, such as to nest it inside another report.
+    pub fn display_indented(&self, spaces: usize) -> impl Display + '_ {
+        self.display_opts(DisplayOpts { indent: spaces, ..DisplayOpts::default() })
+    }
+
+    /// Returns a value that displays this error with the given options.
+    ///
+    /// With [`DisplayOpts::default`], this displays the same as [`Display`]. The other display
+    /// helpers, such as [`Error::display_chain`], are shorthands for particular options.
+    pub fn display_opts<'a>(&'a self, opts: DisplayOpts<'a>) -> impl Display + 'a {
+        DisplayWithOpts { error: self, opts }
+    }
+
+    /// Returns a value that displays only the messages of this error, for showing to end users.
+    ///
+    /// Unlike [`Display`], error codes and locations are not shown. Frames without a message and
+    /// frames added internally by the representation are skipped, and the remaining messages are
+    /// joined with `": "`.
+    pub fn display_clean(&self) -> impl Display + '_ {
+        self.display_opts(DisplayOpts {
+            separator: Some(": "),
+            skip_internal: true,
+            messages_only: true,
+            ..DisplayOpts::default()
+        })
+    }
+
+    /// Attaches an arbitrary displayable value to this error, such as a request ID.
+    ///
+    /// Attachments are shown in the [`Debug`] output of the error, as `attachment: ...` entries.
+    /// They are only stored with the `repr_full` feature, and are silently dropped by the other
+    /// representations.
+    pub fn attach<D: Display + Send + Sync + 'static>(mut self, attachment: D) -> Self {
+        self.underlying.attach(attachment);
+        self
+    }
+
+    /// Returns an iterator over the values attached to this error with [`Error::attach`].
+    pub fn attachments(&self) -> impl Iterator<Item = &(dyn Display + Send + Sync)> + '_ {
+        self.underlying.attachments().iter().map(|x| &**x)
+    }
+
+    /// Adds an advisory note to this error, such as `try increasing the timeout`.
+    ///
+    /// Unlike context, a note is not part of the chain of causes and has no location. Notes are
+    /// not returned by [`Error::frames`], and are only shown in the [`Debug`] output of the error,
+    /// as `= note: ...` entries. Like attachments, they are only stored with the `repr_full`
+    /// feature.
+    pub fn with_note(mut self, note: &'static str) -> Self {
+        self.underlying.push_note(note);
+        self
+    }
+
+    /// Returns an iterator over the notes added to this error with [`Error::with_note`].
+    pub fn notes(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.underlying.notes().iter().copied()
+    }
+
     /// Adds a new context frame to this error type.
-    #[inline(never)]
+    #[inline(always)]
     #[track_caller]
     pub fn with_context(mut self, info: ErrorInfo) -> Self {
-        self.underlying
-            .push_context(&info.info, info.arguments.as_ref());
+        self.push_context_info(info);
+        self
+    }
+
+    /// Adds a frame recording that the error passed through the caller, without a message or
+    /// code, as a breadcrumb for debugging.
+    ///
+    /// The frame is shown as `<reached here>` followed by the location of the caller. The unboxed
+    /// representations store the marker like any other context without a code, so it is replaced
+    /// by the next context added. They leak an error info the first time each location is marked.
+    #[inline(never)]
+    #[track_caller]
+    pub fn mark(mut self) -> Self {
+        self.underlying.push_marker();
+        self
+    }
+
+    /// Adds a new context frame with a message to this error type only if `cond` is true, such as
+    /// for context that is only wanted in verbose mode.
+    ///
+    /// As with [`Error::try_context`], an error info is leaked the first time each `msg` is used.
+    #[inline(always)]
+    #[track_caller]
+    pub fn context_if(mut self, cond: bool, msg: &'static str) -> Self {
+        if cond {
+            self.try_context(msg);
+        }
+        self
+    }
+
+    /// Pins the current error code, so that [`Error::code`] keeps returning it as more context is
+    /// added.
+    ///
+    /// Normally, [`Error::with_context`] makes the code of the newest context with a code the
+    /// current code. After this is called, contexts with a code are still recorded as frames with
+    /// `repr_full`, but no longer change the current code. The unboxed representations cannot
+    /// store both, and drop contexts with a code instead, marking them as omitted. They also
+    /// cannot freeze the code of an error converted from a foreign type without any context, and
+    /// leave those errors unchanged.
+    pub fn freeze_code(&mut self) {
+        self.underlying.freeze_code();
+    }
+
+    /// Removes the current error code from this error and returns it, so another code can be
+    /// added by later context.
+    ///
+    /// The code is still shown by the frames it was added with, and [`Error::root_code`] is
+    /// unaffected. This also undoes [`Error::freeze_code`].
+    pub fn take_code(&mut self) -> Option<&'static ErrorCodeInfo> {
+        self.underlying.take_code()
+    }
+
+    /// Replaces the type name shown for an error converted from a foreign type, such as to hide a
+    /// long generic type behind a more meaningful name.
+    ///
+    /// This does nothing if this error was not converted from a foreign type. The unboxed
+    /// representations do not store the type name once context is added to a conversion that did
+    /// not map to an error code, and leave those errors unchanged as well.
+    pub fn retype_origin(mut self, name: &'static str) -> Self {
+        self.underlying.retype_origin(name);
+        self
+    }
+
+    /// Attaches another error as the cause of this error, placing its frames after the frames of
+    /// this error.
+    ///
+    /// With the `repr_full` feature, a frame marking the start of the cause is inserted between
+    /// the two chains. The other representations only keep the origin of the cause and the latest
+    /// contexts of this error, and mark the frames in between as omitted. If this error was
+    /// converted from a type without any context, the cause is discarded instead.
+    pub fn with_cause(mut self, cause: Error) -> Self {
+        self.underlying.graft_cause(cause.underlying);
+        self
+    }
+
+    /// Adds a context frame to this error type for each of several messages, in order.
+    ///
+    /// The unboxed representations only keep the latest frames, and mark the others as omitted.
+    /// As with [`Error::try_context`], an error info is leaked the first time each message is used.
+    #[inline(never)]
+    #[track_caller]
+    pub fn with_context_each(mut self, msgs: &'static [&'static str]) -> Self {
+        for msg in msgs {
+            self.try_context(msg);
+        }
         self
     }
 
+    /// Adds a new context frame to this error type in place.
+    ///
+    /// This is the low-level operation [`Error::with_context`] is built on, and can be used to
+    /// write custom macros on top of [`error_info!`](crate::error_info). The static part of the
+    /// [`ErrorInfo`] is stored by reference, which is why it must be built by the `error_info!`
+    /// macro, while any formatting arguments are only used during this call.
+    #[inline(never)]
+    #[track_caller]
+    pub fn push_context_info(&mut self, info: ErrorInfo) {
+        self.underlying
+            .push_context(info.info, info.arguments.as_ref());
+    }
+
+    /// Adds a new context frame with a message in place, returning whether the frame was stored.
+    ///
+    /// The unboxed representations have room for a single context, and drop a context without a
+    /// code rather than replace one with a code, marking it as omitted. `repr_full` always stores
+    /// the frame.
+    ///
+    /// As with [`Error::wrap_dyn`], an error info is leaked the first time each `msg` is used, so
+    /// it should be a string literal.
+    #[inline(never)]
+    #[track_caller]
+    pub fn try_context(&mut self, msg: &'static str) -> bool {
+        self.underlying
+            .push_context(crate::intern::message_info(None, msg, None), None)
+    }
+
     /// Adds a new context frame to this error type.
     #[inline(never)]
     #[track_caller]
@@ -103,39 +867,171 @@ impl Error {
         self.underlying.push_context(T::error_source(info), None);
         self
     }
+
+    /// Adds a single context frame with both an error code and a message, rather than one frame
+    /// for each.
+    ///
+    /// This is the same as using [`error_info!`](crate::error_info) with a code and a message, but
+    /// the message may be chosen at runtime from a set of static strings. `repr_full` stores the
+    /// message with the frame. The unboxed representations instead leak an error info holding
+    /// both the first time each code and message are used together, so the message should be a
+    /// string literal.
+    #[inline(never)]
+    #[track_caller]
+    pub fn context_full<T: ErrorCode>(mut self, code: T, msg: &'static str) -> Self {
+        #[cfg(feature = "repr_full")]
+        self.underlying
+            .push_context(T::error_source(code), Some(&format_args!("{msg}")));
+        #[cfg(not(feature = "repr_full"))]
+        {
+            let code = T::error_source(code).error_code;
+            self.underlying
+                .push_context(crate::intern::message_info(code, msg, None), None);
+        }
+        self
+    }
+
+    /// Adds a new context frame with an error code that is only known at runtime, such as one
+    /// received by a proxy that does not have the enum it came from.
+    ///
+    /// The variant name of the code is its value. As error codes must be `'static`, the code is
+    /// leaked the first time each type name, value and message are used together, so this should
+    /// only be used for a bounded set of codes.
+    ///
+    /// The code never compares equal to a code of a real [`ErrorCode`] type, even if the type name
+    /// and value are the same.
+    #[inline(never)]
+    #[track_caller]
+    pub fn attach_raw_code(
+        mut self,
+        type_name: &'static str,
+        value: i64,
+        message: Option<&'static str>,
+    ) -> Self {
+        /// The type used for the type ID of raw codes.
+        struct RawCode;
+        /// The infos of the raw codes used so far.
+        static RAW_CODES: InternTable<(&str, i64, Option<&str>)> = InternTable::new();
+
+        let source = RAW_CODES.get_or_insert_with((type_name, value, message), || {
+            let variant_name = value.to_string().leak();
+            let mut info =
+                ErrorCodeInfo::new(TypeId::of::<RawCode>(), type_name, variant_name, value as i128);
+            info.message = message;
+            let info: &'static ErrorCodeInfo = Box::leak(Box::new(info));
+            ErrorInfoImpl::new_code(info)
+        });
+        self.underlying.push_context(source, None);
+        self
+    }
 }
+/// Converts foreign errors, mapping them to an error code where possible.
 impl<T: core::error::Error> From<T> for Error {
     #[inline(never)]
     #[track_caller]
     fn from(value: T) -> Self {
-        let code = error_code_for_error(&value);
-        Error {
-            underlying: ErrorImpl::new(
-                ErrorOrigin::TypeOrigin(type_name::<T>(), code),
-                Some(&format_args!("{value}")),
-            ),
-        }
+        Error::from_foreign(&value, type_name::<T>(), error_code_for_type(&value))
+    }
+}
+impl Error {
+    /// Creates an error converted from a foreign error of a given type name.
+    #[inline(always)]
+    #[track_caller]
+    fn from_foreign(
+        value: &dyn core::error::Error,
+        name: &'static str,
+        code: Option<&'static ErrorInfoImpl>,
+    ) -> Self {
+        let mut underlying =
+            ErrorImpl::new(ErrorOrigin::TypeOrigin(name, code), Some(&format_args!("{value}")));
+        underlying.push_sources(value);
+        Error::new(underlying)
     }
 }
 impl Debug for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        let mut list = f.debug_list();
-        for frame in self.underlying.iter() {
-            list.entry(&format_args!("{:?}", frame));
-        }
-        list.finish()
+        self.format_with(f, TraceStyle::Debug)
     }
 }
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        let mut iter = self.underlying.iter();
-        if let Some(frame) = iter.next() {
-            write!(f, "{frame}")?;
-        }
-        for frame in iter {
-            write!(f, "\n    caused by: {frame}")?;
+        self.format_with(f, TraceStyle::MultiLine)
+    }
+}
+
+/// The style used to format the frames of an [`Error`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TraceStyle {
+    /// All frames on a single line, separated by `: `.
+    OneLine,
+
+    /// One frame per line, with each cause prefixed by `caused by:`.
+    ///
+    /// This is the style used by the [`Display`] implementation of [`Error`].
+    MultiLine,
+
+    /// One frame per line, with each cause indented one level deeper than the frame before it.
+    ///
+    /// Unlike the other styles, this tracks the depth of each frame while formatting.
+    Tree,
+
+    /// Only the error codes attached to each frame, separated by `, `.
+    CodesOnly,
+
+    /// A list of the frames in their [`Debug`] format, followed by the attachments and notes of
+    /// the error.
+    ///
+    /// This is the style used by the [`Debug`] implementation of [`Error`].
+    Debug,
+}
+
+/// Options for displaying an [`Error`] with [`Error::display_opts`].
+///
+/// The default options display everything, in the same format as [`Display`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DisplayOpts<'a> {
+    /// Whether to show the error code of each frame after its message.
+    ///
+    /// Frames that only have an error code always show it.
+    pub show_codes: bool,
+
+    /// Whether to show the location each frame was created at, if known.
+    pub show_locations: bool,
+
+    /// Whether to show each frame on its own line, in the style of [`TraceStyle::MultiLine`].
+    /// Otherwise, frames are separated by `: ` as with [`TraceStyle::OneLine`].
+    pub multiline: bool,
+
+    /// A separator to write between frames, instead of the one chosen by `multiline`.
+    pub separator: Option<&'a str>,
+
+    /// Whether to skip frames added internally by the representation, such as notes that frames
+    /// were omitted.
+    pub skip_internal: bool,
+
+    /// Whether to start from the root cause rather than the outermost frame.
+    pub reverse: bool,
+
+    /// Whether to show only the message of each frame, skipping frames without one. Error codes
+    /// and locations are not shown, regardless of the other options.
+    pub messages_only: bool,
+
+    /// The number of spaces to indent every line after the first by.
+    pub indent: usize,
+}
+impl Default for DisplayOpts<'_> {
+    fn default() -> Self {
+        DisplayOpts {
+            show_codes: true,
+            show_locations: true,
+            multiline: true,
+            separator: None,
+            skip_internal: false,
+            reverse: false,
+            messages_only: false,
+            indent: 0,
         }
-        Ok(())
     }
 }
 
@@ -143,6 +1039,22 @@ impl Display for Error {
 pub struct ErrorFrame {
     inner: ErrorFrameImpl,
 }
+impl ErrorFrame {
+    /// Returns the message of this frame, falling back to the message of its error code.
+    pub fn message(&self) -> Option<&str> {
+        self.inner.message()
+    }
+
+    /// Returns the error code attached to this frame, if any.
+    pub fn code(&self) -> Option<&'static ErrorCodeInfo> {
+        self.inner.code()
+    }
+
+    /// Returns the location this frame was created at, if known.
+    pub fn location(&self) -> Option<DecodedLocation> {
+        self.inner.location()
+    }
+}
 impl Debug for ErrorFrame {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         Debug::fmt(&self.inner, f)
@@ -154,6 +1066,129 @@ impl Display for ErrorFrame {
     }
 }
 
+/// The value returned by [`Error::codes_only`].
+struct DisplayStyle<'a> {
+    error: &'a Error,
+    style: TraceStyle,
+}
+impl Display for DisplayStyle<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.error.format_with(f, self.style)
+    }
+}
+
+/// Writes to another writer, indenting every line after the first.
+struct IndentWriter<'a, W: Write + ?Sized> {
+    inner: &'a mut W,
+    spaces: usize,
+}
+impl<W: Write + ?Sized> Write for IndentWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let mut lines = s.split('\n');
+        if let Some(first) = lines.next() {
+            self.inner.write_str(first)?;
+        }
+        for line in lines {
+            write!(self.inner, "\n{:1$}", "", self.spaces)?;
+            self.inner.write_str(line)?;
+        }
+        Ok(())
+    }
+}
+
+/// The value returned by [`Error::display_opts`].
+struct DisplayWithOpts<'a> {
+    error: &'a Error,
+    opts: DisplayOpts<'a>,
+}
+impl DisplayWithOpts<'_> {
+    fn fmt_frames(
+        &self,
+        f: &mut Formatter<'_>,
+        frames: impl Iterator<Item = ErrorFrameImpl>,
+    ) -> core::fmt::Result {
+        let sep = match self.opts.separator {
+            Some(sep) => sep,
+            None if self.opts.multiline => "\n    caused by: ",
+            None => ": ",
+        };
+        let mut first = true;
+        for frame in frames {
+            if self.opts.messages_only {
+                let Some(message) = frame.message() else {
+                    continue;
+                };
+                if !first {
+                    f.write_str(sep)?;
+                }
+                f.write_str(message)?;
+            } else {
+                if !first {
+                    f.write_str(sep)?;
+                }
+                frame.fmt_with(f, self.opts.show_codes, self.opts.show_locations)?;
+            }
+            first = false;
+        }
+        Ok(())
+    }
+}
+impl Display for DisplayWithOpts<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let opts = self.opts;
+        if opts.indent != 0 {
+            let mut w = IndentWriter { inner: f, spaces: opts.indent };
+            let unindented = DisplayOpts { indent: 0, ..opts };
+            return write!(w, "{}", DisplayWithOpts { error: self.error, opts: unindented });
+        }
+
+        let frames = self
+            .error
+            .underlying
+            .iter()
+            .filter(|x| !(opts.skip_internal && x.is_internal()));
+        if opts.reverse {
+            let frames: Vec<_> = frames.collect();
+            self.fmt_frames(f, frames.into_iter().rev())
+        } else {
+            self.fmt_frames(f, frames)
+        }
+    }
+}
+
+/// The position of a frame in an [`Error`], returned by [`Error::frames_meta`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FrameMeta {
+    /// Whether this is the outermost frame.
+    pub is_first: bool,
+
+    /// Whether this is the innermost frame that was not added by the representation, such as a
+    /// note that frames were omitted.
+    pub is_last_real: bool,
+
+    /// The number of frames before this one.
+    pub depth: usize,
+}
+
+/// An owned frame of an [`Error`], returned by [`Error::frames_owned`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct OwnedFrame {
+    /// The message of this frame, falling back to the message of its error code.
+    pub message: Option<String>,
+
+    /// The location this frame was created at, if known.
+    pub location: Option<DecodedLocation>,
+
+    /// The error code of this frame, if any.
+    pub code: Option<&'static ErrorCodeInfo>,
+}
+
+/// An iterator over the frames of an [`Error`].
+///
+/// With the `repr_full` feature, this is also a [`DoubleEndedIterator`], allowing frames to be
+/// iterated starting from the deepest cause. The other representations do not support this.
 pub struct ErrorFrameIter<'a> {
     iter: <ErrorImpl as ErrorImplFunctions>::FrameIter<'a>,
 }
@@ -162,7 +1197,32 @@ impl Iterator for ErrorFrameIter<'_> {
     fn next(&mut self) -> Option<Self::Item> {
         self.iter.next().map(|x| ErrorFrame { inner: x })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+impl ExactSizeIterator for ErrorFrameIter<'_> {
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+#[cfg(feature = "repr_full")]
+impl DoubleEndedIterator for ErrorFrameIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|x| ErrorFrame { inner: x })
+    }
+}
+
+/// Extension methods for iterators over the frames of an [`Error`].
+pub trait FrameIterExt: Iterator<Item = ErrorFrame> + Sized {
+    /// Skips frames added internally by the representation, such as notes that frames were
+    /// omitted or that the original error type was lost.
+    fn real(self) -> impl Iterator<Item = ErrorFrame> {
+        self.filter(|x| !x.inner.is_internal())
+    }
 }
+impl<I: Iterator<Item = ErrorFrame>> FrameIterExt for I {}
 
 #[derive(Copy, Clone)]
 pub struct ErrorInfo<'a> {
@@ -170,11 +1230,100 @@ pub struct ErrorInfo<'a> {
     arguments: Option<Arguments<'a>>,
 }
 
+/// Returns whether a type name recorded at conversion matches a path given by the user.
+///
+/// See [`Error::caused_by_type`] for the rules.
+fn type_path_matches(name: &str, path: &str) -> bool {
+    fn segments(name: &str) -> impl Iterator<Item = &str> {
+        let name = name.split('<').next().unwrap_or(name);
+        name.split("::").map(|x| match x {
+            "core" | "alloc" => "std",
+            x => x,
+        })
+    }
+
+    if name == path {
+        return true;
+    }
+    if segments(name).last() != segments(path).last() {
+        return false;
+    }
+    let mut name_segments = segments(name);
+    segments(path).all(|segment| name_segments.any(|x| x == segment))
+}
+
+/// A simple 64-bit FNV-1a hasher, used for hashes that must be stable across runs.
+struct Fnv1aHasher(u64);
+impl Fnv1aHasher {
+    fn new() -> Self {
+        Fnv1aHasher(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
 #[inline(never)]
-fn error_code_for_error<T>(_value: &T) -> Option<&'static ErrorInfoImpl> {
+fn error_code_for_error(
+    value: &(dyn core::error::Error + 'static),
+) -> Option<&'static ErrorInfoImpl> {
+    #[cfg(feature = "std")]
+    if let Some(err) = value.downcast_ref::<std::io::Error>() {
+        let code = crate::IoCode::from(err.kind());
+        return Some(crate::error_code::ErrorCodePrivate::error_source(code));
+    }
+
+    let _ = value;
     None
 }
 
+/// Returns the error code for a foreign error of a type that may not be `'static`.
+#[inline(always)]
+fn error_code_for_type<T: core::error::Error>(value: &T) -> Option<&'static ErrorInfoImpl> {
+    #[cfg(feature = "std")]
+    if non_static_type_id::<T>() == TypeId::of::<std::io::Error>() {
+        // SAFETY: `T` is `std::io::Error`, as their type IDs are equal
+        let value = unsafe { &*(value as *const T).cast::<std::io::Error>() };
+        return error_code_for_error(value);
+    }
+
+    let _ = value;
+    None
+}
+
+/// Returns the type ID of a type that may not be `'static`, ignoring its lifetimes.
+#[cfg(feature = "std")]
+fn non_static_type_id<T: ?Sized>() -> TypeId {
+    trait NonStaticAny {
+        fn type_id(&self) -> TypeId
+        where
+            Self: 'static;
+    }
+    impl<T: ?Sized> NonStaticAny for core::marker::PhantomData<T> {
+        fn type_id(&self) -> TypeId
+        where
+            Self: 'static,
+        {
+            TypeId::of::<T>()
+        }
+    }
+
+    let phantom = core::marker::PhantomData::<T>;
+    let erased: &dyn NonStaticAny = &phantom;
+    // SAFETY: type IDs do not depend on lifetimes, and the extended lifetime is only used to read
+    // the type ID, never to access a value of `T`
+    let erased: &(dyn NonStaticAny + 'static) = unsafe { core::mem::transmute(erased) };
+    erased.type_id()
+}
+
 pub const fn new_error_info<'a>(
     info: &'static ErrorInfoImpl,
     arguments: Option<Arguments<'a>>,
@@ -0,0 +1,110 @@
+//! Entry points for fuzzing the error representations.
+//!
+//! Errors are built from a sequence of bytes, each of which selects an operation. This lets a
+//! fuzzer explore the packing logic and the iterator phases of each representation.
+
+use crate::Error;
+use crate::error_code::ErrorCodeInfo;
+use crate::error_impl::{
+    DecodedLocation, ErrorImplFunctions, ErrorInfoImpl, ErrorOrigin, StaticMessageInfo,
+};
+use crate::error_ty::new_error_info;
+use core::any::TypeId;
+use core::fmt::Write;
+
+struct FuzzCode;
+
+static CODE_A: ErrorCodeInfo = ErrorCodeInfo {
+    message: Some("code a"),
+    ..ErrorCodeInfo::new(TypeId::of::<FuzzCode>(), "FuzzCode", "A", 0)
+};
+static CODE_B: ErrorCodeInfo = ErrorCodeInfo::new(TypeId::of::<FuzzCode>(), "FuzzCode", "B", 1);
+
+static LOCATION: DecodedLocation = DecodedLocation { module: "fuzz.rs", line: 1, column: 1 };
+
+static SOURCES: [ErrorInfoImpl; 4] = [
+    ErrorInfoImpl {
+        error_code: None,
+        message_static: StaticMessageInfo::NoFormat("message"),
+        location: Some(&LOCATION),
+    },
+    ErrorInfoImpl {
+        error_code: Some(&CODE_A),
+        message_static: StaticMessageInfo::None,
+        location: None,
+    },
+    ErrorInfoImpl {
+        error_code: Some(&CODE_B),
+        message_static: StaticMessageInfo::Unformatted("\"message {}\""),
+        location: Some(&LOCATION),
+    },
+    ErrorInfoImpl { error_code: None, message_static: StaticMessageInfo::None, location: None },
+];
+
+static TYPE_NAMES: [&str; 3] = ["", "std::io::Error", "a::very::long::path::to::SomeError<u8>"];
+
+/// Builds an error from a sequence of operations.
+///
+/// The first byte selects how the error is created. Each following byte pushes a context frame,
+/// first taking the error code if its second highest bit is set, and then freezing the error code
+/// if its highest bit is set.
+/// Returns `None` for an empty input.
+pub fn build_error(data: &[u8]) -> Option<Error> {
+    let (&first, rest) = data.split_first()?;
+
+    let source = &SOURCES[(first >> 2) as usize % SOURCES.len()];
+    let ty = TYPE_NAMES[(first >> 2) as usize % TYPE_NAMES.len()];
+    let origin = match first % 3 {
+        0 => ErrorOrigin::StaticOrigin(source),
+        1 => ErrorOrigin::TypeOrigin(ty, None),
+        _ => ErrorOrigin::TypeOrigin(ty, Some(source)),
+    };
+
+    let mut error = Error::from_origin(origin);
+    error.assert_invariants();
+    for &op in rest {
+        if op & 0x40 != 0 {
+            error.take_code();
+            error.assert_invariants();
+        }
+        if op & 0x80 != 0 {
+            error.freeze_code();
+            error.assert_invariants();
+        }
+        let source = &SOURCES[op as usize % SOURCES.len()];
+        error = error.with_context(new_error_info(source, None));
+        error.assert_invariants();
+    }
+    Some(error)
+}
+
+/// Removes all but the `len` innermost steps of an error, which may leave it without any.
+///
+/// Only `repr_full` supports this, and the other representations leave the error unchanged.
+pub fn truncate(error: &mut Error, len: usize) {
+    error.underlying.truncate(len);
+}
+
+/// Builds an error from a sequence of operations, then exercises every way of reading it.
+pub fn run(data: &[u8]) {
+    let Some(error) = build_error(data) else {
+        return;
+    };
+
+    let mut out = alloc::string::String::new();
+    write!(out, "{error}").unwrap();
+    write!(out, "{error:?}").unwrap();
+
+    let _ = error.code();
+    let _ = error.source_location();
+    error.clone().assert_invariants();
+
+    let mut truncated = error.clone();
+    truncate(&mut truncated, data.len() / 2);
+    truncated.assert_invariants();
+    write!(out, "{truncated}").unwrap();
+
+    let grafted = error.clone().with_cause(error);
+    grafted.assert_invariants();
+    write!(out, "{grafted}").unwrap();
+}
@@ -0,0 +1,43 @@
+//! A global hook that is run whenever an error is created.
+
+use crate::Error;
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// The current hook, stored as a type-erased `fn(&Error)`, or null if no hook is set.
+static ERROR_HOOK: AtomicPtr<()> = AtomicPtr::new(null_mut());
+
+/// Sets a function to be called with every [`Error`] when it is created, replacing any previous
+/// hook.
+///
+/// The hook runs before the error is returned to its creator, so it sees the error before any
+/// context is added. This can be used to collect metrics on every error created by a program.
+///
+/// When no hook is set, creating an error only costs an additional atomic load. When one is set,
+/// it is run on every error, including errors that are handled and discarded, so it should be
+/// cheap. The hook must not create errors itself, as this will recurse.
+pub fn set_error_hook(hook: fn(&Error)) {
+    ERROR_HOOK.store(hook as *mut (), Ordering::Release);
+}
+
+/// Removes the hook set by [`set_error_hook`], if any.
+pub fn remove_error_hook() {
+    ERROR_HOOK.store(null_mut(), Ordering::Release);
+}
+
+/// Runs the current hook on a newly created error.
+#[inline(always)]
+pub(crate) fn run_error_hook(error: &Error) {
+    let hook = ERROR_HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+        call_hook(hook, error);
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn call_hook(hook: *mut (), error: &Error) {
+    // SAFETY: non-null values are only ever stored by `set_error_hook`, from a `fn(&Error)`.
+    let hook = unsafe { core::mem::transmute::<*mut (), fn(&Error)>(hook) };
+    hook(error);
+}
@@ -0,0 +1,189 @@
+//! Interning of error infos created at runtime.
+
+use crate::error_code::ErrorCodeInfo;
+use crate::error_impl::{DecodedLocation, ErrorInfoImpl, StaticMessageInfo};
+use alloc::boxed::Box;
+#[cfg(not(feature = "repr_full"))]
+use core::panic::Location;
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+/// The most payloads interned for each `#[code(transparent)]` variant.
+///
+/// Each distinct payload of a transparent error code leaks one [`ErrorCodeInfo`], and creating
+/// an error looks its payload up in a list of the ones used so far. To bound both, once this many
+/// payloads of a variant have been used, errors with any further payload share the info of the
+/// variant itself. Their value is then the discriminant of the variant rather than the payload,
+/// so [`Error::downcast_code`](crate::Error::downcast_code) and [`Error::is`](crate::Error::is)
+/// no longer see the payload.
+pub const MAX_CODE_PAYLOADS: usize = 256;
+
+/// A table of error infos created at runtime, such as the one for each payload of a
+/// `#[code(transparent)]` variant.
+///
+/// Error infos must be `'static`, so one is allocated and leaked the first time each key is used.
+/// The infos are [`ErrorInfoImpl`] unless the representation needs to store more alongside them.
+/// The table is a lock-free linked list, as the number of distinct keys used by a program is
+/// expected to be small. Tables whose keys come from runtime values are bounded with a limit.
+pub struct InternTable<K = u64, V: 'static = ErrorInfoImpl> {
+    head: AtomicPtr<InternNode<K, V>>,
+    len: AtomicUsize,
+}
+struct InternNode<K, V: 'static> {
+    key: K,
+    source: &'static V,
+    next: *mut InternNode<K, V>,
+}
+impl<K: Copy + Eq, V: 'static> InternTable<K, V> {
+    /// Creates a new empty table.
+    pub const fn new() -> Self {
+        InternTable { head: AtomicPtr::new(null_mut()), len: AtomicUsize::new(0) }
+    }
+
+    /// Returns the error info for a given key, creating it with `make` if needed.
+    ///
+    /// If several threads create the info for the same key at once, only one of the infos is
+    /// kept, and the others are leaked.
+    pub(crate) fn get_or_insert_with(&self, key: K, make: impl FnOnce() -> V) -> &'static V {
+        self.get_or_try_insert_with(key, usize::MAX, make)
+            .expect("intern table is full")
+    }
+
+    /// Returns the error info for a given key, creating it with `make` if the table has fewer
+    /// than `limit` entries, or returns `None` otherwise.
+    ///
+    /// Threads racing to insert different keys may each pass the check, so the table can
+    /// exceed the limit by up to the number of such threads.
+    pub(crate) fn get_or_try_insert_with(
+        &self,
+        key: K,
+        limit: usize,
+        make: impl FnOnce() -> V,
+    ) -> Option<&'static V> {
+        let mut head = self.head.load(Ordering::Acquire);
+        if let Some(source) = Self::find(head, key) {
+            return Some(source);
+        }
+        if self.len.load(Ordering::Relaxed) >= limit {
+            return None;
+        }
+
+        let source = Box::leak(Box::new(make()));
+        let node = Box::into_raw(Box::new(InternNode { key, source, next: head }));
+        loop {
+            match self
+                .head
+                .compare_exchange(head, node, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => {
+                    self.len.fetch_add(1, Ordering::Relaxed);
+                    return Some(source);
+                }
+                Err(new_head) => {
+                    // another thread may have interned the same key in the meantime
+                    if let Some(source) = Self::find(new_head, key) {
+                        // SAFETY: the node was never published, so nothing else refers to it
+                        drop(unsafe { Box::from_raw(node) });
+                        return Some(source);
+                    }
+                    // SAFETY: the node was never published, so we have exclusive access to it
+                    unsafe { (*node).next = new_head };
+                    head = new_head;
+                }
+            }
+        }
+    }
+
+    fn find(mut node: *mut InternNode<K, V>, key: K) -> Option<&'static V> {
+        while !node.is_null() {
+            // SAFETY: published nodes are never freed or modified
+            let current = unsafe { &*node };
+            if current.key == key {
+                return Some(current.source);
+            }
+            node = current.next;
+        }
+        None
+    }
+}
+impl InternTable {
+    /// Returns the error info for a given payload, creating it from the info of its variant if
+    /// needed.
+    ///
+    /// The infos of `#[code(transparent)]` payloads are marked with `payload`, so they are not
+    /// equal to a unit variant with the same value. Once [`MAX_CODE_PAYLOADS`] payloads have been
+    /// interned, the info of the variant is returned for any further payload.
+    pub fn get(
+        &self,
+        variant: &'static ErrorInfoImpl,
+        value: u64,
+        negative: bool,
+        payload: bool,
+    ) -> &'static ErrorInfoImpl {
+        let template = variant
+            .error_code
+            .expect("transparent variants have an error code");
+        let interned = self.get_or_try_insert_with(value, MAX_CODE_PAYLOADS, || {
+            let value = if negative { value as i64 as i128 } else { value as i128 };
+            let info: &'static ErrorCodeInfo = Box::leak(Box::new(ErrorCodeInfo {
+                message: template.message,
+                deprecated: template.deprecated,
+                doc_url: template.doc_url,
+                group: template.group,
+                severity: template.severity,
+                metadata: template.metadata,
+                payload,
+                ..ErrorCodeInfo::new(template.tid, template.type_name, template.variant_name, value)
+            }));
+            ErrorInfoImpl::new_code(info)
+        });
+        interned.unwrap_or(variant)
+    }
+}
+impl<K: Copy + Eq, V: 'static> Default for InternTable<K, V> {
+    fn default() -> Self {
+        InternTable::new()
+    }
+}
+
+/// The key of an interned static message, error code and location, by address.
+type MessageKey = (usize, usize, usize, usize);
+
+/// The error infos created for static messages chosen at runtime.
+static MESSAGES: InternTable<MessageKey> = InternTable::new();
+
+/// Returns an error info with a static message chosen at runtime, and an optional error code and
+/// location.
+///
+/// The representations can only store messages that are part of an `'static` error info, so
+/// one is leaked the first time each message, code and location are used. Messages are compared
+/// by address, as they are expected to be string literals.
+pub fn message_info(
+    code: Option<&'static ErrorCodeInfo>,
+    message: &'static str,
+    location: Option<&'static DecodedLocation>,
+) -> &'static ErrorInfoImpl {
+    let code_addr = code.map_or(0, |x| x as *const ErrorCodeInfo as usize);
+    let location_addr = location.map_or(0, |x| x as *const DecodedLocation as usize);
+    let key = (code_addr, message.as_ptr() as usize, message.len(), location_addr);
+    MESSAGES.get_or_insert_with(key, || {
+        ErrorInfoImpl::new(code, StaticMessageInfo::NoFormat(message), location)
+    })
+}
+
+/// The error infos created for the locations of markers, by address.
+#[cfg(not(feature = "repr_full"))]
+static MARKERS: InternTable<usize> = InternTable::new();
+
+/// Returns an error info with only a location, for a marker frame added by `Error::mark`.
+///
+/// One is leaked the first time each location is used. Locations are compared by address, as
+/// they are expected to come from `#[track_caller]`.
+#[cfg(not(feature = "repr_full"))]
+pub fn marker_info(location: &'static Location<'static>) -> &'static ErrorInfoImpl {
+    let key = location as *const Location<'static> as usize;
+    MARKERS.get_or_insert_with(key, || {
+        let location: &'static DecodedLocation = Box::leak(Box::new(location.into()));
+        ErrorInfoImpl::new(None, StaticMessageInfo::None, Some(location))
+    })
+}
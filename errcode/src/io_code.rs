@@ -0,0 +1,184 @@
+//! Error codes for errors converted from [`std::io::Error`].
+
+use crate::ErrorCode;
+use std::io::ErrorKind;
+
+/// An error code corresponding to an [`ErrorKind`].
+///
+/// Errors converted from [`std::io::Error`] are given the code matching their kind, which can be
+/// recovered with [`Error::downcast_code`](crate::Error::downcast_code).
+///
+/// Kinds that are unknown to this crate are mapped to [`IoCode::Other`].
+#[derive(ErrorCode, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum IoCode {
+    /// Corresponds to [`ErrorKind::NotFound`].
+    NotFound,
+    /// Corresponds to [`ErrorKind::PermissionDenied`].
+    PermissionDenied,
+    /// Corresponds to [`ErrorKind::ConnectionRefused`].
+    ConnectionRefused,
+    /// Corresponds to [`ErrorKind::ConnectionReset`].
+    ConnectionReset,
+    /// Corresponds to [`ErrorKind::HostUnreachable`].
+    HostUnreachable,
+    /// Corresponds to [`ErrorKind::NetworkUnreachable`].
+    NetworkUnreachable,
+    /// Corresponds to [`ErrorKind::ConnectionAborted`].
+    ConnectionAborted,
+    /// Corresponds to [`ErrorKind::NotConnected`].
+    NotConnected,
+    /// Corresponds to [`ErrorKind::AddrInUse`].
+    AddrInUse,
+    /// Corresponds to [`ErrorKind::AddrNotAvailable`].
+    AddrNotAvailable,
+    /// Corresponds to [`ErrorKind::NetworkDown`].
+    NetworkDown,
+    /// Corresponds to [`ErrorKind::BrokenPipe`].
+    BrokenPipe,
+    /// Corresponds to [`ErrorKind::AlreadyExists`].
+    AlreadyExists,
+    /// Corresponds to [`ErrorKind::WouldBlock`].
+    WouldBlock,
+    /// Corresponds to [`ErrorKind::NotADirectory`].
+    NotADirectory,
+    /// Corresponds to [`ErrorKind::IsADirectory`].
+    IsADirectory,
+    /// Corresponds to [`ErrorKind::DirectoryNotEmpty`].
+    DirectoryNotEmpty,
+    /// Corresponds to [`ErrorKind::ReadOnlyFilesystem`].
+    ReadOnlyFilesystem,
+    /// Corresponds to [`ErrorKind::StaleNetworkFileHandle`].
+    StaleNetworkFileHandle,
+    /// Corresponds to [`ErrorKind::InvalidInput`].
+    InvalidInput,
+    /// Corresponds to [`ErrorKind::InvalidData`].
+    InvalidData,
+    /// Corresponds to [`ErrorKind::TimedOut`].
+    TimedOut,
+    /// Corresponds to [`ErrorKind::WriteZero`].
+    WriteZero,
+    /// Corresponds to [`ErrorKind::StorageFull`].
+    StorageFull,
+    /// Corresponds to [`ErrorKind::NotSeekable`].
+    NotSeekable,
+    /// Corresponds to [`ErrorKind::QuotaExceeded`].
+    QuotaExceeded,
+    /// Corresponds to [`ErrorKind::FileTooLarge`].
+    FileTooLarge,
+    /// Corresponds to [`ErrorKind::ResourceBusy`].
+    ResourceBusy,
+    /// Corresponds to [`ErrorKind::ExecutableFileBusy`].
+    ExecutableFileBusy,
+    /// Corresponds to [`ErrorKind::Deadlock`].
+    Deadlock,
+    /// Corresponds to [`ErrorKind::CrossesDevices`].
+    CrossesDevices,
+    /// Corresponds to [`ErrorKind::TooManyLinks`].
+    TooManyLinks,
+    /// Corresponds to [`ErrorKind::InvalidFilename`].
+    InvalidFilename,
+    /// Corresponds to [`ErrorKind::ArgumentListTooLong`].
+    ArgumentListTooLong,
+    /// Corresponds to [`ErrorKind::Interrupted`].
+    Interrupted,
+    /// Corresponds to [`ErrorKind::Unsupported`].
+    Unsupported,
+    /// Corresponds to [`ErrorKind::UnexpectedEof`].
+    UnexpectedEof,
+    /// Corresponds to [`ErrorKind::OutOfMemory`].
+    OutOfMemory,
+    /// Corresponds to [`ErrorKind::Other`].
+    Other,
+}
+impl IoCode {
+    /// Returns the [`ErrorKind`] corresponding to this code.
+    pub fn kind(self) -> ErrorKind {
+        match self {
+            IoCode::NotFound => ErrorKind::NotFound,
+            IoCode::PermissionDenied => ErrorKind::PermissionDenied,
+            IoCode::ConnectionRefused => ErrorKind::ConnectionRefused,
+            IoCode::ConnectionReset => ErrorKind::ConnectionReset,
+            IoCode::HostUnreachable => ErrorKind::HostUnreachable,
+            IoCode::NetworkUnreachable => ErrorKind::NetworkUnreachable,
+            IoCode::ConnectionAborted => ErrorKind::ConnectionAborted,
+            IoCode::NotConnected => ErrorKind::NotConnected,
+            IoCode::AddrInUse => ErrorKind::AddrInUse,
+            IoCode::AddrNotAvailable => ErrorKind::AddrNotAvailable,
+            IoCode::NetworkDown => ErrorKind::NetworkDown,
+            IoCode::BrokenPipe => ErrorKind::BrokenPipe,
+            IoCode::AlreadyExists => ErrorKind::AlreadyExists,
+            IoCode::WouldBlock => ErrorKind::WouldBlock,
+            IoCode::NotADirectory => ErrorKind::NotADirectory,
+            IoCode::IsADirectory => ErrorKind::IsADirectory,
+            IoCode::DirectoryNotEmpty => ErrorKind::DirectoryNotEmpty,
+            IoCode::ReadOnlyFilesystem => ErrorKind::ReadOnlyFilesystem,
+            IoCode::StaleNetworkFileHandle => ErrorKind::StaleNetworkFileHandle,
+            IoCode::InvalidInput => ErrorKind::InvalidInput,
+            IoCode::InvalidData => ErrorKind::InvalidData,
+            IoCode::TimedOut => ErrorKind::TimedOut,
+            IoCode::WriteZero => ErrorKind::WriteZero,
+            IoCode::StorageFull => ErrorKind::StorageFull,
+            IoCode::NotSeekable => ErrorKind::NotSeekable,
+            IoCode::QuotaExceeded => ErrorKind::QuotaExceeded,
+            IoCode::FileTooLarge => ErrorKind::FileTooLarge,
+            IoCode::ResourceBusy => ErrorKind::ResourceBusy,
+            IoCode::ExecutableFileBusy => ErrorKind::ExecutableFileBusy,
+            IoCode::Deadlock => ErrorKind::Deadlock,
+            IoCode::CrossesDevices => ErrorKind::CrossesDevices,
+            IoCode::TooManyLinks => ErrorKind::TooManyLinks,
+            IoCode::InvalidFilename => ErrorKind::InvalidFilename,
+            IoCode::ArgumentListTooLong => ErrorKind::ArgumentListTooLong,
+            IoCode::Interrupted => ErrorKind::Interrupted,
+            IoCode::Unsupported => ErrorKind::Unsupported,
+            IoCode::UnexpectedEof => ErrorKind::UnexpectedEof,
+            IoCode::OutOfMemory => ErrorKind::OutOfMemory,
+            IoCode::Other => ErrorKind::Other,
+        }
+    }
+}
+impl From<ErrorKind> for IoCode {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::NotFound => IoCode::NotFound,
+            ErrorKind::PermissionDenied => IoCode::PermissionDenied,
+            ErrorKind::ConnectionRefused => IoCode::ConnectionRefused,
+            ErrorKind::ConnectionReset => IoCode::ConnectionReset,
+            ErrorKind::HostUnreachable => IoCode::HostUnreachable,
+            ErrorKind::NetworkUnreachable => IoCode::NetworkUnreachable,
+            ErrorKind::ConnectionAborted => IoCode::ConnectionAborted,
+            ErrorKind::NotConnected => IoCode::NotConnected,
+            ErrorKind::AddrInUse => IoCode::AddrInUse,
+            ErrorKind::AddrNotAvailable => IoCode::AddrNotAvailable,
+            ErrorKind::NetworkDown => IoCode::NetworkDown,
+            ErrorKind::BrokenPipe => IoCode::BrokenPipe,
+            ErrorKind::AlreadyExists => IoCode::AlreadyExists,
+            ErrorKind::WouldBlock => IoCode::WouldBlock,
+            ErrorKind::NotADirectory => IoCode::NotADirectory,
+            ErrorKind::IsADirectory => IoCode::IsADirectory,
+            ErrorKind::DirectoryNotEmpty => IoCode::DirectoryNotEmpty,
+            ErrorKind::ReadOnlyFilesystem => IoCode::ReadOnlyFilesystem,
+            ErrorKind::StaleNetworkFileHandle => IoCode::StaleNetworkFileHandle,
+            ErrorKind::InvalidInput => IoCode::InvalidInput,
+            ErrorKind::InvalidData => IoCode::InvalidData,
+            ErrorKind::TimedOut => IoCode::TimedOut,
+            ErrorKind::WriteZero => IoCode::WriteZero,
+            ErrorKind::StorageFull => IoCode::StorageFull,
+            ErrorKind::NotSeekable => IoCode::NotSeekable,
+            ErrorKind::QuotaExceeded => IoCode::QuotaExceeded,
+            ErrorKind::FileTooLarge => IoCode::FileTooLarge,
+            ErrorKind::ResourceBusy => IoCode::ResourceBusy,
+            ErrorKind::ExecutableFileBusy => IoCode::ExecutableFileBusy,
+            ErrorKind::Deadlock => IoCode::Deadlock,
+            ErrorKind::CrossesDevices => IoCode::CrossesDevices,
+            ErrorKind::TooManyLinks => IoCode::TooManyLinks,
+            ErrorKind::InvalidFilename => IoCode::InvalidFilename,
+            ErrorKind::ArgumentListTooLong => IoCode::ArgumentListTooLong,
+            ErrorKind::Interrupted => IoCode::Interrupted,
+            ErrorKind::Unsupported => IoCode::Unsupported,
+            ErrorKind::UnexpectedEof => IoCode::UnexpectedEof,
+            ErrorKind::OutOfMemory => IoCode::OutOfMemory,
+            _ => IoCode::Other,
+        }
+    }
+}
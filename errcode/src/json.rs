@@ -0,0 +1,114 @@
+//! Writing errors as JSON without depending on `serde`.
+
+use crate::Error;
+use crate::error_code::ErrorCodeInfo;
+use crate::error_impl::{ErrorFrameImpl, ErrorImplFunctions};
+use core::fmt::{Display, Formatter, Result, Write};
+
+impl Error {
+    /// Writes this error as a compact JSON object on a single line, for structured logging.
+    ///
+    /// The object has the form
+    /// `{"code":...,"message":...,"frames":[{"message":...,"code":...,"location":...},...]}`,
+    /// where `code` is the current error code as `"Type::Variant"`, `message` is the message of
+    /// the outermost frame, and each location is a `"file:line:column"` string. Missing values are
+    /// written as `null`. Frames added internally by the representation are not included.
+    ///
+    /// This does not allocate, so it can be used with a fixed size buffer.
+    pub fn write_json<W: Write>(&self, w: &mut W) -> Result {
+        w.write_str("{\"code\":")?;
+        write_code(w, self.underlying.code())?;
+        w.write_str(",\"message\":")?;
+        match self.underlying.iter().find(|x| !x.is_internal()) {
+            Some(frame) => write_message(w, &frame)?,
+            None => w.write_str("null")?,
+        }
+        w.write_str(",\"frames\":[")?;
+        for (i, frame) in self
+            .underlying
+            .iter()
+            .filter(|x| !x.is_internal())
+            .enumerate()
+        {
+            if i != 0 {
+                w.write_char(',')?;
+            }
+            w.write_str("{\"message\":")?;
+            write_message(w, &frame)?;
+            w.write_str(",\"code\":")?;
+            write_code(w, frame.code())?;
+            w.write_str(",\"location\":")?;
+            match frame.location() {
+                Some(loc) => {
+                    write_string(w, format_args!("{}:{}:{}", loc.module, loc.line, loc.column))?
+                }
+                None => w.write_str("null")?,
+            }
+            w.write_char('}')?;
+        }
+        w.write_str("]}")
+    }
+
+    /// Returns a value that displays this error as a JSON object.
+    ///
+    /// See [`Error::write_json`] for the format used.
+    pub fn display_json_line(&self) -> impl Display + '_ {
+        DisplayJson(self)
+    }
+}
+
+/// The value returned by [`Error::display_json_line`].
+struct DisplayJson<'a>(&'a Error);
+impl Display for DisplayJson<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        self.0.write_json(f)
+    }
+}
+
+fn write_code<W: Write>(w: &mut W, code: Option<&'static ErrorCodeInfo>) -> Result {
+    match code {
+        Some(code) => write_string(w, format_args!("{}::{}", code.type_name, code.variant_name)),
+        None => w.write_str("null"),
+    }
+}
+
+fn write_message<W: Write>(w: &mut W, frame: &ErrorFrameImpl) -> Result {
+    match frame.message() {
+        Some(message) => write_string(w, message),
+        None => w.write_str("null"),
+    }
+}
+
+/// Writes a value as a quoted JSON string.
+fn write_string<W: Write>(w: &mut W, value: impl Display) -> Result {
+    w.write_char('"')?;
+    write!(JsonEscape(w), "{value}")?;
+    w.write_char('"')
+}
+
+/// A writer that escapes everything written to it for use inside a JSON string.
+struct JsonEscape<'a, W: Write>(&'a mut W);
+impl<W: Write> Write for JsonEscape<'_, W> {
+    fn write_str(&mut self, s: &str) -> Result {
+        let mut start = 0;
+        for (i, ch) in s.char_indices() {
+            let escape = match ch {
+                '"' => "\\\"",
+                '\\' => "\\\\",
+                '\n' => "\\n",
+                '\r' => "\\r",
+                '\t' => "\\t",
+                ch if (ch as u32) < 0x20 => "",
+                _ => continue,
+            };
+            self.0.write_str(&s[start..i])?;
+            if escape.is_empty() {
+                write!(self.0, "\\u{:04x}", ch as u32)?;
+            } else {
+                self.0.write_str(escape)?;
+            }
+            start = i + ch.len_utf8();
+        }
+        self.0.write_str(&s[start..])
+    }
+}
@@ -1,15 +1,75 @@
 #![no_std]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+// allows `#[derive(ErrorCode)]` to be used inside this crate
+extern crate self as errcode;
+
+#[cfg(feature = "miette")]
+mod diagnostic;
 mod error_code;
 mod error_impl;
+mod error_list;
 mod error_ty;
+mod fuzz;
+mod hook;
+mod intern;
+#[cfg(feature = "std")]
+mod io_code;
+mod json;
 mod macros;
+mod message_store;
+#[cfg(feature = "serde")]
+mod status;
+#[cfg(feature = "testing")]
+mod testing;
+#[cfg(feature = "tracing")]
+mod trace_event;
 mod traits;
 
+#[cfg(feature = "miette")]
+pub use diagnostic::ErrorDiagnostic;
 pub use errcode_derive::ErrorCode;
-pub use error_code::ErrorCode;
-pub use error_ty::{Error, ErrorFrame, ErrorFrameIter, ErrorInfo};
+pub use error_code::{CodeError, ErrorCode, ErrorCodeInfo, Severity};
+pub use error_impl::{DecodedLocation, LOCATION_THRESHOLD};
+#[cfg(feature = "heapless")]
+pub use error_impl::{HEAPLESS_MESSAGE_CAPACITY, HEAPLESS_STEP_CAPACITY};
+pub use error_list::Errors;
+pub use error_ty::{
+    DisplayOpts, Error, ErrorFrame, ErrorFrameIter, ErrorInfo, FrameIterExt, FrameMeta, OwnedFrame,
+    TraceStyle,
+};
+pub use hook::{remove_error_hook, set_error_hook};
+pub use intern::MAX_CODE_PAYLOADS;
+#[cfg(feature = "std")]
+pub use io_code::IoCode;
+pub use message_store::{MessageStore, set_message_store};
+#[cfg(feature = "serde")]
+pub use status::StatusDetails;
+#[cfg(feature = "testing")]
+pub use testing::{ExpectedFrame, TraceBuilder};
+
+/// Whether errors store the location they were created at.
+///
+/// Without this, [`Error::source_location`] is only available for errors created from an
+/// [`ErrorInfo`] with a location.
+pub const HAS_LOCATIONS: bool = cfg!(any(
+    feature = "repr_full",
+    feature = "repr_unboxed_location",
+    feature = "compact_location"
+));
+
+/// The name of the error representation in use, such as `"repr_unboxed"`.
+pub const REPR_NAME: &str = if cfg!(feature = "repr_full") {
+    "repr_full"
+} else if cfg!(feature = "repr_unboxed_location") {
+    "repr_unboxed_location"
+} else if cfg!(feature = "compact_location") {
+    "compact_location"
+} else {
+    "repr_unboxed"
+};
 
 /// A module containing helpful imports for using this crate.
 pub mod prelude {
@@ -18,17 +78,25 @@ pub mod prelude {
     /// A convince wrapper over the [`Result`](`core::result::Result`) type.
     pub type Result<T> = core::result::Result<T, Error>;
 
+    pub use crate::FrameIterExt;
     pub use crate::traits::{ConvertErrorHelper, IntoErrorHelper};
 
     pub use crate::{bail, ensure, error, error_info};
 }
 
+/// NOT PUBLIC API!
+#[doc(hidden)]
+pub mod __fuzz {
+    pub use crate::fuzz::{build_error, run, truncate};
+}
+
 /// NOT PUBLIC API!
 #[doc(hidden)]
 pub mod __macro_export {
-    pub use crate::error_code::{ErrorCodeInfo, ErrorCodePrivate};
+    pub use crate::error_code::{ErrorCodeInfo, ErrorCodePrivate, Severity};
     pub use crate::error_impl::{DecodedLocation, ErrorInfoImpl, StaticMessageInfo};
     pub use crate::error_ty::new_error_info;
+    pub use crate::intern::InternTable;
     pub use crate::macros::{get_helper, static_message, wrap_code};
     pub use core;
     pub use core::option::Option::{None, Some};
@@ -7,6 +7,11 @@ use crate::{Error, ErrorInfo};
 /// Creates a new [`ErrorInfo`].
 ///
 /// TODO: Document
+///
+/// The static part of the info (code, message template and location) is built as a constant, and
+/// is shared by every error created from the same call site. Identical constants from different
+/// call sites may also be merged by the compiler or linker. Errors never rely on the address of
+/// this data to tell frames apart, so it is safe for contexts to share it.
 #[macro_export]
 macro_rules! error_info {
     () => {
@@ -119,11 +124,7 @@ pub const fn get_helper<T: ErrorCodePrivate>(_t: &T) -> T::ConstHelper {
 }
 
 pub const fn wrap_code(code: &'static ErrorCodeInfo) -> ErrorInfoImpl {
-    ErrorInfoImpl {
-        error_code: Some(code),
-        message_static: StaticMessageInfo::None,
-        location: None,
-    }
+    ErrorInfoImpl::new_code(code)
 }
 
 /// Constructs a new [`Error`].
@@ -0,0 +1,41 @@
+//! Pluggable storage for formatted error messages.
+
+use alloc::boxed::Box;
+use core::fmt::Arguments;
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// A custom allocator for the formatted messages of errors, such as a bump allocator over a fixed
+/// arena.
+///
+/// By default, each formatted message is stored in its own heap allocation. Messages are only
+/// formatted with the `repr_full` feature, and the other representations never use the store.
+pub trait MessageStore: Sync {
+    /// Formats a message and stores it for the rest of the program.
+    ///
+    /// Returns `None` if the message could not be stored, in which case it is stored on the heap
+    /// instead.
+    fn store(&self, args: &Arguments<'_>) -> Option<&'static str>;
+}
+
+/// The current message store, or null if the default heap storage is used.
+static MESSAGE_STORE: AtomicPtr<&'static dyn MessageStore> = AtomicPtr::new(null_mut());
+
+/// Sets the [`MessageStore`] used to store the formatted messages of new errors.
+///
+/// This is meant to be called once at startup, and leaks a small allocation on every call.
+pub fn set_message_store(store: &'static dyn MessageStore) {
+    MESSAGE_STORE.store(Box::leak(Box::new(store)), Ordering::Release);
+}
+
+/// Stores a message in the current [`MessageStore`], if one is set.
+#[cfg_attr(not(feature = "repr_full"), allow(dead_code))]
+pub(crate) fn store_message(args: &Arguments<'_>) -> Option<&'static str> {
+    let store = MESSAGE_STORE.load(Ordering::Acquire);
+    if store.is_null() {
+        None
+    } else {
+        // SAFETY: non-null values are only ever stored by `set_message_store`, from a leaked box.
+        unsafe { (*store).store(args) }
+    }
+}
@@ -0,0 +1,49 @@
+//! Conversion of errors to status payloads for RPC frameworks.
+
+use crate::Error;
+use crate::error_impl::ErrorImplFunctions;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde::Serialize;
+
+/// A numeric code and a details map describing an [`Error`], as expected by RPC frameworks such as
+/// JSON-RPC or gRPC.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct StatusDetails {
+    /// The value of the current error code as a signed number, or `0` if the error has no code
+    /// or its value is an unsigned value above [`i64::MAX`].
+    pub code: i64,
+
+    /// The message of the outermost frame.
+    pub message: String,
+
+    /// The messages of the remaining frames, keyed as `cause.0`, `cause.1` and so on.
+    pub metadata: Vec<(String, String)>,
+}
+
+impl Error {
+    /// Converts this error into a [`StatusDetails`] payload.
+    ///
+    /// Frames added internally by the representation, such as notes that frames were omitted, are
+    /// not included.
+    pub fn to_status_details(&self) -> StatusDetails {
+        let mut messages = self
+            .underlying
+            .iter()
+            .filter(|x| !x.is_internal())
+            .map(|x| match x.message() {
+                Some(message) => message.to_string(),
+                None => x.to_string(),
+            });
+        StatusDetails {
+            code: self.code().and_then(|x| x.value_i64()).unwrap_or(0),
+            message: messages.next().unwrap_or_default(),
+            metadata: messages
+                .enumerate()
+                .map(|(i, message)| (format!("cause.{i}"), message))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Helpers for asserting the structure of errors in tests.
+
+use crate::Error;
+use crate::error_code::{ErrorCode, ErrorCodeInfo};
+use crate::error_impl::{DecodedLocation, ErrorImplFunctions, ErrorInfoImpl, StaticMessageInfo};
+use crate::error_ty::new_error_info;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+/// A description of a frame expected by [`Error::frames_eq`].
+///
+/// Fields that are `None` match any value.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ExpectedFrame<'a> {
+    /// The message of the frame.
+    pub message: Option<&'a str>,
+
+    /// The error code attached to the frame.
+    pub code: Option<&'static ErrorCodeInfo>,
+
+    /// The location of the frame.
+    ///
+    /// Only the module and line are compared, and an empty module matches any module.
+    pub location: Option<DecodedLocation>,
+}
+impl<'a> ExpectedFrame<'a> {
+    /// Creates an expected frame that matches any frame.
+    pub const fn new() -> Self {
+        ExpectedFrame { message: None, code: None, location: None }
+    }
+
+    /// Sets the expected message.
+    pub const fn message(mut self, message: &'a str) -> Self {
+        self.message = Some(message);
+        self
+    }
+
+    /// Sets the expected error code.
+    pub fn code<T: ErrorCode>(mut self, code: T) -> Self {
+        self.code = T::error_source(code).error_code;
+        self
+    }
+
+    /// Sets the expected location.
+    pub const fn location(mut self, location: DecodedLocation) -> Self {
+        self.location = Some(location);
+        self
+    }
+}
+
+/// Builds an [`Error`] from an explicit list of frames, such as for testing how errors are
+/// formatted.
+///
+/// Errors built this way only have the locations given for each frame, rather than the location
+/// they were created at, so their output does not change as the test is edited. The
+/// representation still decides which frames are kept, so the unboxed representations omit
+/// frames past the second context as usual.
+///
+/// The frames are leaked when the error is built, so this should only be used in tests.
+#[derive(Clone, Debug, Default)]
+pub struct TraceBuilder {
+    frames: Vec<(Option<&'static str>, Option<&'static ErrorCodeInfo>, DecodedLocation)>,
+}
+impl TraceBuilder {
+    /// Creates a builder without any frames.
+    pub const fn new() -> Self {
+        TraceBuilder { frames: Vec::new() }
+    }
+
+    /// Adds a frame as context on top of the frames added so far. The first frame added is the
+    /// root cause of the error.
+    pub fn frame(
+        mut self,
+        message: Option<&'static str>,
+        code: Option<&'static ErrorCodeInfo>,
+        location: DecodedLocation,
+    ) -> Self {
+        self.frames.push((message, code, location));
+        self
+    }
+
+    /// Builds the error.
+    ///
+    /// Panics if no frames were added.
+    pub fn build(&self) -> Error {
+        let mut infos = self.frames.iter().map(|&(message, code, location)| {
+            let message = match message {
+                Some(message) => StaticMessageInfo::NoFormat(message),
+                None => StaticMessageInfo::None,
+            };
+            let location: &'static DecodedLocation = Box::leak(Box::new(location));
+            let info: &'static ErrorInfoImpl =
+                Box::leak(Box::new(ErrorInfoImpl::new(code, message, Some(location))));
+            new_error_info(info, None)
+        });
+        let origin = infos
+            .next()
+            .expect("a `TraceBuilder` needs at least one frame");
+        let mut error = Error::from_info(origin);
+        for info in infos {
+            error.push_context_info(info);
+        }
+        error.underlying.clear_caller_locations();
+        error
+    }
+}
+
+impl Error {
+    /// Returns whether the frames of this error match a list of expected frames, starting from
+    /// the outermost context.
+    ///
+    /// Frames added internally by the representation, such as notes that frames were omitted, are
+    /// skipped.
+    pub fn frames_eq(&self, expected: &[ExpectedFrame<'_>]) -> bool {
+        let mut frames = self.underlying.iter().filter(|x| !x.is_internal());
+        for expected in expected {
+            let Some(frame) = frames.next() else {
+                return false;
+            };
+            if expected.message.is_some() && frame.message() != expected.message {
+                return false;
+            }
+            if let Some(code) = expected.code {
+                match frame.code() {
+                    Some(actual) if actual.same_code(code) => {}
+                    _ => return false,
+                }
+            }
+            if let Some(location) = expected.location {
+                match frame.location() {
+                    Some(actual) if actual.is_same(location) => {}
+                    _ => return false,
+                }
+            }
+        }
+        frames.next().is_none()
+    }
+}
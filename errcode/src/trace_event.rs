@@ -0,0 +1,66 @@
+//! Integration with the `tracing` crate.
+
+use crate::{Error, ErrorCodeInfo, TraceStyle};
+use core::fmt::{Display, Formatter};
+use tracing::{Level, event, field};
+
+/// Displays an error code as `Type::Variant`.
+struct CodeName(&'static ErrorCodeInfo);
+impl Display for CodeName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}::{}", self.0.type_name, self.0.variant_name)
+    }
+}
+
+/// Displays the outermost frame of an error.
+struct Outermost<'a>(&'a Error);
+impl Display for Outermost<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self.0.frames().next() {
+            Some(frame) => write!(f, "{frame}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Displays the frames of an error on one line.
+struct OneLine<'a>(&'a Error);
+impl Display for OneLine<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.0.format_with(f, TraceStyle::OneLine)
+    }
+}
+
+impl Error {
+    /// Emits this error as a `tracing` event at the given level.
+    ///
+    /// The event has the following fields:
+    /// * `error.code`: The current error code, if any.
+    /// * `error.root_code`: The error code of the deepest frame that has one, if any.
+    /// * `error.depth`: The number of frames in this error.
+    /// * `error.frames`: The frames of this error, formatted with [`TraceStyle::OneLine`].
+    ///
+    /// The message of the event is the outermost frame of this error.
+    pub fn emit_event(&self, level: Level) {
+        macro_rules! emit {
+            ($level:expr) => {
+                event!(
+                    $level,
+                    error.code = self.code().map(|x| field::display(CodeName(x))),
+                    error.root_code = self.root_code().map(|x| field::display(CodeName(x))),
+                    error.depth = self.frame_count(),
+                    error.frames = %OneLine(self),
+                    "{}",
+                    Outermost(self),
+                )
+            };
+        }
+        match level {
+            Level::ERROR => emit!(Level::ERROR),
+            Level::WARN => emit!(Level::WARN),
+            Level::INFO => emit!(Level::INFO),
+            Level::DEBUG => emit!(Level::DEBUG),
+            _ => emit!(Level::TRACE),
+        }
+    }
+}
@@ -77,3 +77,542 @@ fn error_with_context() {
         assert!(lines[i].trim().starts_with(expected_lines[i]), "Line {}: {}", i, line);
     }
 }
+
+#[test]
+#[cfg(all(feature = "compact_location", target_arch = "x86_64"))]
+fn compact_location() {
+    assert_eq!(size_of::<Error>(), size_of::<[usize; 2]>());
+    assert_eq!(size_of::<Option<Error>>(), size_of::<Error>());
+
+    let line = line!() + 1;
+    let error = Error::from_code(TestCode::C);
+    assert!(error.is(TestCode::C));
+
+    let line_str = error.to_string();
+    assert!(line_str.contains(&format!(" [at :{line}:")), "Line: {line_str}");
+
+    let error = error.with_context(error_info!("context"));
+    let line_str = error.to_string();
+    assert!(line_str.contains(&format!(" [at :{line}:")), "Line: {line_str}");
+}
+
+#[test]
+fn source_location() {
+    let line = line!() + 1;
+    let error = Error::from_info(error_info!("root cause")).with_context(error_info!("context"));
+    let location = error.source_location().unwrap();
+    assert_eq!(location.line, line);
+    #[cfg(not(all(feature = "compact_location", target_arch = "x86_64")))]
+    assert_eq!(location.module, file!());
+}
+
+#[test]
+fn source_location_code_only() {
+    let line = line!() + 1;
+    let error = Error::from_code(TestCode::A);
+    let location = error.source_location();
+
+    #[cfg(any(
+        feature = "repr_full",
+        feature = "repr_unboxed_location",
+        all(feature = "compact_location", target_arch = "x86_64")
+    ))]
+    assert_eq!(location.unwrap().line, line);
+
+    #[cfg(not(any(
+        feature = "repr_full",
+        feature = "repr_unboxed_location",
+        all(feature = "compact_location", target_arch = "x86_64")
+    )))]
+    {
+        let _ = line;
+        assert!(location.is_none());
+    }
+}
+
+#[test]
+fn location_chain() {
+    let root_line = line!() + 1;
+    let error = Error::from_info(error_info!("root cause"));
+    let context_line = line!() + 1;
+    let error = error.with_context(error_info!(TestCode::A, "context"));
+    let lines: Vec<_> = error.location_chain().map(|x| x.line).collect();
+    assert_eq!(lines, [context_line, root_line]);
+
+    let code_line = line!() + 1;
+    let error = Error::from_code(TestCode::A);
+    let lines: Vec<_> = error.location_chain().map(|x| x.line).collect();
+
+    #[cfg(any(
+        feature = "repr_full",
+        feature = "repr_unboxed_location",
+        all(feature = "compact_location", target_arch = "x86_64")
+    ))]
+    assert_eq!(lines, [code_line]);
+
+    #[cfg(not(any(
+        feature = "repr_full",
+        feature = "repr_unboxed_location",
+        all(feature = "compact_location", target_arch = "x86_64")
+    )))]
+    {
+        let _ = code_line;
+        assert!(lines.is_empty());
+    }
+}
+
+#[test]
+fn attachments() {
+    let error = Error::from_info(error_info!("root cause"))
+        .attach("request id: 42")
+        .attach(format!("config: {}", "debug"));
+    let debug = format!("{error:?}");
+
+    #[cfg(feature = "repr_full")]
+    {
+        let attachments: Vec<_> = error.attachments().map(|x| x.to_string()).collect();
+        assert_eq!(attachments, ["request id: 42", "config: debug"]);
+        assert!(debug.contains("attachment: request id: 42"), "Debug: {debug}");
+        assert!(debug.contains("attachment: config: debug"), "Debug: {debug}");
+    }
+
+    #[cfg(not(feature = "repr_full"))]
+    {
+        assert_eq!(error.attachments().count(), 0);
+        assert!(!debug.contains("attachment:"), "Debug: {debug}");
+    }
+}
+
+#[test]
+fn notes() {
+    let plain = Error::from_info(error_info!("request timed out"))
+        .with_context(error_info!("while fetching"));
+    let error = plain.clone().with_note("try increasing the timeout");
+    let debug = format!("{error:?}");
+
+    // notes are not part of the frames
+    assert_eq!(error.to_string(), plain.to_string());
+    assert_eq!(error.frames().count(), plain.frames().count());
+
+    #[cfg(feature = "repr_full")]
+    {
+        assert_eq!(error.notes().collect::<Vec<_>>(), ["try increasing the timeout"]);
+        assert!(debug.contains("= note: try increasing the timeout"), "Debug: {debug}");
+
+        // notes and attachments are shown with different prefixes
+        let debug = format!("{:?}", error.attach("request id: 42"));
+        assert!(debug.contains("attachment: request id: 42"), "Debug: {debug}");
+        assert!(!debug.contains("note: request id"), "Debug: {debug}");
+    }
+
+    #[cfg(not(feature = "repr_full"))]
+    {
+        assert_eq!(error.notes().count(), 0);
+        assert!(!debug.contains("note:"), "Debug: {debug}");
+    }
+}
+
+#[test]
+fn error_info_predicates() {
+    use errcode::__macro_export::{DecodedLocation, ErrorInfoImpl, StaticMessageInfo, wrap_code};
+
+    static LOCATION: DecodedLocation = DecodedLocation { module: "", line: 1, column: 1 };
+    let code = errcode::__macro_export::get_helper(&TestCode::A).info(TestCode::A);
+
+    let code_only = wrap_code(code);
+    assert!(code_only.has_code());
+    assert!(!code_only.has_message());
+    assert!(code_only.is_code_only());
+
+    let message_only = ErrorInfoImpl {
+        error_code: None,
+        message_static: StaticMessageInfo::NoFormat("message"),
+        location: None,
+    };
+    assert!(!message_only.has_code());
+    assert!(message_only.has_message());
+    assert!(!message_only.is_code_only());
+
+    let unformatted = ErrorInfoImpl {
+        message_static: StaticMessageInfo::Unformatted("\"message {}\""),
+        ..message_only
+    };
+    assert!(unformatted.has_message());
+    assert!(!unformatted.is_code_only());
+
+    let code_and_message = ErrorInfoImpl { error_code: Some(code), ..message_only };
+    assert!(code_and_message.has_code());
+    assert!(code_and_message.has_message());
+    assert!(!code_and_message.is_code_only());
+
+    let code_and_location = ErrorInfoImpl { location: Some(&LOCATION), ..code_only };
+    assert!(code_and_location.has_code());
+    assert!(!code_and_location.has_message());
+    assert!(!code_and_location.is_code_only());
+
+    let empty = ErrorInfoImpl { error_code: None, ..code_only };
+    assert!(!empty.has_code());
+    assert!(!empty.has_message());
+    assert!(!empty.is_code_only());
+}
+
+#[test]
+fn is_conversion() {
+    assert!(Error::from_type("ForeignError").is_conversion());
+    assert!(
+        Error::from_type("ForeignError")
+            .with_context(error_info!("while loading"))
+            .is_conversion()
+    );
+    assert!(!Error::from_info(error_info!("x")).is_conversion());
+    assert!(!Error::from_code(TestCode::A).is_conversion());
+
+    // conversions mapped to an error code keep the conversion marker and type name
+    #[cfg(feature = "std")]
+    {
+        let error = Error::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert!(error.is_conversion());
+        assert!(error.is(errcode::IoCode::NotFound));
+        let name = core::any::type_name::<std::io::Error>();
+        assert_eq!(error.origin_type_name(), Some(name));
+
+        let error = error.with_context(error_info!("while loading"));
+        assert!(error.is_conversion());
+        assert_eq!(error.origin_type_name(), Some(name));
+        assert_eq!(error.retype_origin("io::Error").origin_type_name(), Some("io::Error"));
+    }
+}
+
+#[test]
+fn origin_type_name() {
+    assert_eq!(Error::from_type("ForeignError").origin_type_name(), Some("ForeignError"));
+    assert_eq!(Error::from_info(error_info!("native")).origin_type_name(), None);
+    assert_eq!(Error::from_code(TestCode::A).origin_type_name(), None);
+
+    // the unboxed representations lose the type name once context is added
+    let error = Error::from_type("ForeignError").with_context(error_info!("while loading"));
+    #[cfg(feature = "repr_full")]
+    assert_eq!(error.origin_type_name(), Some("ForeignError"));
+    #[cfg(not(feature = "repr_full"))]
+    {
+        assert!(error.to_string().contains("<original error type lost>"));
+        assert_eq!(error.origin_type_name(), None);
+    }
+}
+
+#[test]
+fn caused_by_type() {
+    let error = Error::from_type("ForeignError");
+    assert!(error.caused_by_type("ForeignError"));
+    assert!(!error.caused_by_type("OtherError"));
+    assert!(!Error::from_info(error_info!("native")).caused_by_type("ForeignError"));
+
+    // the path a type is re-exported at matches the path it is defined at
+    let error = Error::from("x".parse::<u32>().unwrap_err());
+    assert!(error.caused_by_type(core::any::type_name::<core::num::ParseIntError>()));
+    assert!(error.caused_by_type("std::num::ParseIntError"));
+    assert!(error.caused_by_type("core::num::ParseIntError"));
+    assert!(error.caused_by_type("ParseIntError"));
+    assert!(!error.caused_by_type("std::fmt::ParseIntError"));
+    assert!(!error.caused_by_type("std::num::ParseFloatError"));
+
+    #[cfg(feature = "std")]
+    {
+        let error = Error::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert!(error.caused_by_type("std::io::Error"));
+        assert!(!error.caused_by_type("std::fmt::Error"));
+        let error = error.with_context(error_info!("while loading"));
+        assert!(error.caused_by_type("std::io::Error"));
+    }
+
+    // frames converted with a message are found by `repr_full` in the chain of causes
+    let error = Error::from("x".parse::<u32>().unwrap_err())
+        .with_context(error_info!("while parsing"))
+        .with_context(error_info!("while loading"));
+    assert_eq!(error.caused_by_type("std::num::ParseIntError"), cfg!(feature = "repr_full"));
+
+    // only the retained origin type is checked by the unboxed representations
+    let error = Error::from_type("ForeignError").with_context(error_info!("while loading"));
+    assert_eq!(error.caused_by_type("ForeignError"), cfg!(feature = "repr_full"));
+    assert!(!error.caused_by_type("OtherError"));
+}
+
+#[test]
+fn long_type_name() {
+    let name: &'static str = "a".repeat(u16::MAX as usize).leak();
+    let error = Error::from_type(name);
+    error.frames().for_each(drop);
+    assert!(error.to_string().contains(name));
+}
+
+/// An error type whose type name grows with its parameter.
+struct NamedError<T>(core::marker::PhantomData<T>);
+impl<T> core::fmt::Debug for NamedError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("NamedError")
+    }
+}
+impl<T> core::fmt::Display for NamedError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("named error")
+    }
+}
+impl<T: 'static> core::error::Error for NamedError<T> {}
+
+fn assert_converted_name<T: 'static>() {
+    let error = Error::from(NamedError::<T>(core::marker::PhantomData));
+    let name = core::any::type_name::<NamedError<T>>();
+    assert_eq!(error.origin_type_name(), Some(name));
+    #[cfg(not(feature = "repr_full"))]
+    assert!(error.to_string().contains(name), "Error: {error}");
+}
+
+#[test]
+fn type_name_round_trip() {
+    assert_converted_name::<u8>();
+    assert_converted_name::<String>();
+    assert_converted_name::<Vec<Option<std::collections::BTreeMap<String, Vec<u8>>>>>();
+    assert_converted_name::<(u8, (u16, (u32, (u64, (u128, [char; 3])))))>();
+
+    for len in [0, 1, 2, 3, 4, 5, 7, 8, 9, 255, 256, 257, 4096, 65535, 65536, 100_000] {
+        let name: &'static str = "a".repeat(len).leak();
+        let error = Error::from_type(name);
+        let stored = error.origin_type_name().unwrap();
+        assert_eq!(stored.len(), len);
+        assert!(core::ptr::eq(stored, name));
+    }
+
+    // multi-byte characters must never be split
+    for count in [1, 2, 3, 1000] {
+        let name: &'static str = "é🦀".repeat(count).leak();
+        assert_eq!(Error::from_type(name).origin_type_name(), Some(name));
+    }
+
+    // replacing the name keeps the rest of the packed value intact
+    let long: &'static str = "b".repeat(1000).leak();
+    let error = Error::from_type("a").retype_origin(long);
+    assert_eq!(error.origin_type_name(), Some(long));
+    let error = error.retype_origin("");
+    assert_eq!(error.origin_type_name(), Some(""));
+}
+
+#[test]
+fn repr_constants() {
+    let error = Error::from_code(TestCode::A);
+    if errcode::HAS_LOCATIONS {
+        assert!(error.source_location().is_some());
+    }
+
+    #[cfg(feature = "repr_full")]
+    assert_eq!(errcode::REPR_NAME, "repr_full");
+    #[cfg(feature = "compact_location")]
+    assert_eq!(errcode::REPR_NAME, "compact_location");
+    #[cfg(not(any(
+        feature = "repr_full",
+        feature = "repr_unboxed_location",
+        feature = "compact_location"
+    )))]
+    {
+        assert_eq!(errcode::REPR_NAME, "repr_unboxed");
+        const { assert!(!errcode::HAS_LOCATIONS) };
+    }
+}
+
+#[test]
+fn push_context_info() {
+    let mut error = Error::from_info(error_info!("root cause"));
+    for i in 0..2 {
+        error.push_context_info(error_info!(TestCode::B, "attempt {}", i));
+    }
+    assert!(error.is(TestCode::B));
+    #[cfg(feature = "repr_full")]
+    assert!(error.to_string().starts_with("attempt 1"), "Error: {error}");
+}
+
+#[test]
+fn retype_origin() {
+    let long = "a::very::long::path::to::SomeError<alloc::vec::Vec<u8>>";
+    let error = Error::from_type(long).retype_origin("SomeError");
+    let out = error.to_string();
+    assert!(out.contains("<from type: SomeError>"), "Error: {out}");
+    assert!(!out.contains(long), "Error: {out}");
+
+    let error = Error::from_info(error_info!("native")).retype_origin("SomeError");
+    assert!(!error.to_string().contains("SomeError"));
+}
+
+#[test]
+fn decoded_location_from_borrowed() {
+    // a copy on the stack, so references to it are not 'static
+    let location = *core::panic::Location::caller();
+    let decoded = errcode::DecodedLocation::from_location(&location);
+    assert_eq!(decoded.module, location.file());
+    assert_eq!(decoded.line, location.line());
+    assert_eq!(decoded.column, location.column());
+
+    let converted = errcode::DecodedLocation::from(&location);
+    assert_eq!(converted.line, location.line());
+}
+
+#[derive(Debug)]
+struct InnerError;
+impl core::fmt::Display for InnerError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("disk unplugged")
+    }
+}
+impl core::error::Error for InnerError {}
+
+#[derive(Debug)]
+struct OuterError(InnerError);
+impl core::fmt::Display for OuterError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("could not save file")
+    }
+}
+impl core::error::Error for OuterError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+#[test]
+fn source_chain() {
+    let error = Error::from(OuterError(InnerError));
+    assert!(error.is_conversion());
+    let out = error.to_string();
+
+    #[cfg(feature = "repr_full")]
+    {
+        assert!(out.starts_with("could not save file"), "Output: {out}");
+        assert!(out.contains("caused by: disk unplugged"), "Output: {out}");
+        assert!(error.origin_type_name().unwrap().ends_with("OuterError"));
+    }
+    #[cfg(not(feature = "repr_full"))]
+    assert!(!out.contains("disk unplugged"), "Output: {out}");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn same_origin_with_sources() {
+    use std::io::ErrorKind;
+    let convert = |kind: ErrorKind, error: Box<dyn core::error::Error + Send + Sync>| {
+        Error::from(std::io::Error::new(kind, error))
+    };
+
+    // only the custom error has a source chain, which `repr_full` stores beneath the origin
+    let chained = convert(ErrorKind::NotFound, Box::new(OuterError(InnerError)));
+    let plain = convert(ErrorKind::NotFound, "not found".into());
+    let other = convert(ErrorKind::PermissionDenied, Box::new(OuterError(InnerError)));
+    #[cfg(feature = "repr_full")]
+    assert!(chained.to_string().contains("disk unplugged"));
+
+    assert!(chained.is_same_origin(&plain));
+    assert!(!chained.is_same_origin(&other));
+}
+
+/// An error borrowing from its input, which is not `'static`.
+#[derive(Debug)]
+struct BorrowedError<'a>(&'a str);
+impl core::fmt::Display for BorrowedError<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "invalid input: {}", self.0)
+    }
+}
+impl core::error::Error for BorrowedError<'_> {}
+
+#[test]
+fn non_static_conversion() {
+    let input = String::from("abc");
+    let error = Error::from(BorrowedError(&input));
+    drop(input);
+    assert!(error.is_conversion());
+}
+
+#[test]
+fn wrap_dyn() {
+    let source: Box<dyn core::error::Error + Send + Sync> = Box::new(OuterError(InnerError));
+    let error = Error::wrap_dyn(source, "while syncing");
+    assert!(error.is_conversion());
+    assert!(!error.has_code());
+    let display = error.to_string();
+    assert!(display.starts_with("while syncing"), "Display: {display}");
+    assert!(!display.contains("<internal error"), "Display: {display}");
+
+    #[cfg(feature = "repr_full")]
+    {
+        let messages: Vec<_> = error
+            .frames()
+            .map(|x| x.message().unwrap().to_string())
+            .collect();
+        assert_eq!(messages, ["while syncing", "could not save file", "disk unplugged"]);
+        assert_eq!(error.origin_type_name(), Some("dyn core::error::Error"));
+    }
+
+    #[cfg(feature = "std")]
+    {
+        let source = Box::new(std::io::Error::from(std::io::ErrorKind::NotFound));
+        let error = Error::wrap_dyn(source, "while opening config");
+        assert!(error.is(errcode::IoCode::NotFound));
+    }
+}
+
+#[test]
+fn clone_shallow() {
+    let original = Error::from_info(error_info!("disk {} full", 1))
+        .with_context(error_info!(TestCode::A, "while saving"));
+    let copy = original.clone_shallow();
+    assert_eq!(copy.to_string(), original.to_string());
+
+    // modifying the copy leaves the original unchanged
+    let copy = copy.with_context(error_info!(TestCode::B, "request failed"));
+    assert!(copy.is(TestCode::B));
+    assert!(original.is(TestCode::A));
+    assert!(!original.to_string().contains("request failed"));
+}
+
+#[test]
+fn from_args() {
+    let path = std::hint::black_box("/tmp/cache");
+    let error = Error::from_args(format_args!("could not open {path}"));
+    let frame = error.frames().next().unwrap();
+    assert!(!error.has_code());
+
+    #[cfg(feature = "repr_full")]
+    assert_eq!(frame.message(), Some("could not open /tmp/cache"));
+    #[cfg(not(feature = "repr_full"))]
+    assert!(frame.to_string().starts_with("<unformatted:> {}"), "Frame: {frame}");
+}
+
+#[track_caller]
+fn from_caller() -> Error {
+    Error::from_info(error_info!("created here"))
+}
+
+#[test]
+fn rewrite_locations() {
+    let strip = |location: errcode::DecodedLocation| errcode::DecodedLocation {
+        module: location
+            .module
+            .strip_prefix("errcode/")
+            .unwrap_or(location.module),
+        ..location
+    };
+    let error = from_caller().with_context(error_info!(TestCode::A, "context"));
+    let rewritten = error.clone().rewrite_locations(strip);
+    assert_eq!(rewritten.to_string().lines().count(), error.to_string().lines().count());
+
+    let modules: Vec<_> = rewritten.location_chain().map(|x| x.module).collect();
+    assert_eq!(modules.len(), error.location_chain().count());
+    #[cfg(feature = "repr_full")]
+    {
+        assert!(modules.iter().all(|x| *x == "tests/basic.rs"), "Modules: {modules:?}");
+        assert!(!rewritten.to_string().contains("errcode/tests"), "Error: {rewritten}");
+
+        // rewriting again applies to the rewritten locations
+        let twice = rewritten.rewrite_locations(strip);
+        assert!(twice.location_chain().all(|x| x.module == "tests/basic.rs"));
+    }
+    #[cfg(not(feature = "repr_full"))]
+    assert_eq!(modules, error.location_chain().map(|x| x.module).collect::<Vec<_>>());
+}
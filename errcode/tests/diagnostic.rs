@@ -0,0 +1,38 @@
+#![cfg(feature = "miette")]
+
+use errcode::{Error, ErrorCode, error_info};
+use miette::{Diagnostic, Report};
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+#[code(doc_url = "https://example.com/errors/{variant}")]
+pub enum DiagnosticCode {
+    #[code(severity = "warning")]
+    DiskFull,
+    Other,
+}
+
+fn failing() -> miette::Result<()> {
+    Err(Error::from_info(error_info!(DiagnosticCode::DiskFull, "disk is full")))?;
+    Ok(())
+}
+
+#[test]
+fn report() {
+    let report = failing().unwrap_err();
+    assert_eq!(report.code().unwrap().to_string(), "DiagnosticCode::DiskFull");
+    assert_eq!(report.severity(), Some(miette::Severity::Warning));
+    assert_eq!(report.url().unwrap().to_string(), "https://example.com/errors/DiskFull");
+    assert!(report.to_string().contains("disk is full"));
+}
+
+#[test]
+fn no_code() {
+    let report = Report::from(Error::from_info(error_info!("no code")));
+    assert!(report.code().is_none());
+    assert!(report.severity().is_none());
+    assert!(report.url().is_none());
+
+    let diagnostic = Error::from_code(DiagnosticCode::Other).into_diagnostic();
+    assert!(diagnostic.severity().is_none());
+    assert!(diagnostic.into_inner().is(DiagnosticCode::Other));
+}
@@ -1,4 +1,4 @@
-use errcode::{Error, ErrorCode, error_info};
+use errcode::{CodeError, Error, ErrorCode, ErrorCodeInfo, ErrorInfo, FrameIterExt, error_info};
 
 #[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Code1 {
@@ -12,6 +12,60 @@ pub enum Code2 {
     Y,
 }
 
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(i64)]
+pub enum WideCode {
+    Large = 5_000_000_000,
+    Negative = (-5),
+    AfterNegative,
+}
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u64)]
+pub enum HugeCode {
+    Max = 0xFFFF_FFFF_FFFF_FFFF,
+}
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EvolvingCode {
+    Current,
+    #[code(deprecated = "use EvolvingCode::Current instead")]
+    Old,
+    #[deprecated]
+    #[code(deprecated = "no longer returned")]
+    Removed,
+}
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AliasedCode {
+    #[code(alias = 1001)]
+    NotFound = 2001,
+    #[code(alias = 1002, alias = (-1))]
+    Forbidden = 2002,
+}
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u32)]
+pub enum OsCode {
+    Unknown = 1_000_000,
+    #[code(transparent)]
+    Errno(i32),
+}
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExitCode {
+    Success,
+    #[code(transparent)]
+    Status(i32),
+}
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PortCode {
+    Closed,
+    #[code(transparent)]
+    Port(u16),
+}
+
 #[test]
 fn has_code_functions() {
     let err = Error::from_info(error_info!("no code"));
@@ -56,6 +110,20 @@ fn test_downcast_code() {
     assert_eq!(code2, None);
 }
 
+#[test]
+fn as_code_enum() {
+    let err = Error::from_code(Code1::B);
+    assert_eq!(err.as_code_enum::<Code1>(), Ok(Code1::B));
+    assert_eq!(
+        err.as_code_enum::<Code2>(),
+        Err(CodeError::WrongType { expected: "Code2", actual: "Code1" })
+    );
+
+    let err = Error::from_info(error_info!("no code"));
+    assert_eq!(err.as_code_enum::<Code1>(), Err(CodeError::NoCode));
+    assert_eq!(CodeError::NoCode.to_string(), "error has no error code");
+}
+
 #[test]
 fn context_code_overwriting() {
     let err = Error::from_code(Code1::A).with_context(error_info!("some context"));
@@ -65,3 +133,691 @@ fn context_code_overwriting() {
     assert!(err.is(Code2::X));
     assert!(!err.is(Code1::A));
 }
+
+#[test]
+fn context_code_preference() {
+    let mut err = Error::from_info(error_info!("root cause"))
+        .with_context(error_info!("plain context"))
+        .with_context(error_info!("plain context"));
+    assert!(!err.has_code());
+
+    // contexts without a code never replace the current code, however many are added, as long
+    // as they fit in the error
+    let rounds = if cfg!(feature = "heapless") { 3 } else { 4 };
+    for _ in 0..rounds {
+        err = err.with_context(error_info!(Code1::A, "coded context"));
+        assert!(err.is(Code1::A));
+        err = err.with_context(error_info!("plain context"));
+        assert!(err.is(Code1::A));
+        err = err.with_context(error_info!(Code2::X, "other coded context"));
+        assert!(err.is(Code2::X));
+        err = err.with_context(error_info!("plain context"));
+        assert!(err.is(Code2::X));
+    }
+    #[cfg(not(feature = "repr_full"))]
+    assert_eq!(err.frames().next().unwrap().message(), Some("other coded context"));
+}
+
+#[test]
+fn code_value() {
+    assert_eq!(Error::from_code(Code2::Y).code_value(), Some(1));
+    assert_eq!(Error::from_info(error_info!("no code")).code_value(), None);
+    assert_eq!(Error::from_code(WideCode::Large).code_value(), Some(5_000_000_000u64 as u32));
+    assert_eq!(Error::from_code(WideCode::Negative).code_value(), Some(-5i32 as u32));
+}
+
+#[test]
+fn wide_code_values() {
+    for code in [WideCode::Large, WideCode::Negative, WideCode::AfterNegative] {
+        let err = Error::from_code(code);
+        assert!(err.is(code));
+        assert_eq!(err.downcast_code::<WideCode>(), Some(code));
+    }
+
+    // values that do not fit in 32 bits are truncated by `value`, and returned in full by the
+    // wider accessors
+    let large = Error::from_code(WideCode::Large).code().unwrap();
+    assert_eq!(large.value(), 5_000_000_000u64 as u32);
+    assert_eq!(large.value, large.value());
+    assert_eq!(large.value_u64(), 5_000_000_000);
+    assert!(!large.is_negative());
+    let negative = Error::from_code(WideCode::Negative).code().unwrap();
+    assert_eq!(negative.value(), -5i32 as u32);
+    assert_eq!(negative.value_i64(), Some(-5));
+    assert!(negative.is_negative());
+    let after = Error::from_code(WideCode::AfterNegative).code().unwrap();
+    assert_eq!(after.value_i64(), Some(-4));
+
+    // unsigned values above `i64::MAX` do not fit in a signed number
+    let huge = Error::from_code(HugeCode::Max).code().unwrap();
+    assert_eq!(huge.value_u64(), u64::MAX);
+    assert_eq!(huge.value_i64(), None);
+    assert!(!huge.is_negative());
+
+    let err = Error::from_code(WideCode::Large);
+    assert!(!err.is(WideCode::Negative));
+    assert!(!err.is(WideCode::AfterNegative));
+    assert_eq!(err.downcast_code::<Code1>(), None);
+}
+
+#[test]
+fn deprecated_codes() {
+    let err = Error::from_code(EvolvingCode::Current);
+    assert_eq!(err.code().unwrap().deprecation(), None);
+
+    let err = Error::from_code(EvolvingCode::Old);
+    assert_eq!(err.code().unwrap().deprecation(), Some("use EvolvingCode::Current instead"));
+
+    #[allow(deprecated)]
+    let err = Error::from_code(EvolvingCode::Removed);
+    assert_eq!(err.code().unwrap().deprecation(), Some("no longer returned"));
+
+    let err = Error::from_info(error_info!("no code"));
+    assert!(err.code().is_none());
+}
+
+#[test]
+fn same_code_as() {
+    let a = Error::from_code(Code1::A);
+    let b = Error::from_info(error_info!(Code1::A, "with a message"));
+    let c = Error::from_code(Code1::B);
+    let d = Error::from_info(error_info!("no code"));
+
+    assert!(a.same_code_as(&b));
+    assert!(!a.same_code_as(&c));
+    assert!(!a.same_code_as(&d));
+    assert!(!d.same_code_as(&d));
+}
+
+#[test]
+fn code_ptr_eq() {
+    let a = Error::from_info(error_info!(Code1::A, "with a message"));
+    let clone = a.clone().with_context(error_info!("more context"));
+    assert!(core::ptr::eq(a.code().unwrap(), clone.code().unwrap()));
+    assert!(a.code_ptr_eq(&clone));
+    assert!(a.code_ptr_eq(&Error::from_code(Code1::A)));
+    assert!(Error::from_code(OsCode::Errno(5)).code_ptr_eq(&Error::from_code(OsCode::Errno(5))));
+
+    assert!(!a.code_ptr_eq(&Error::from_code(Code1::B)));
+    assert!(!Error::from_code(OsCode::Errno(5)).code_ptr_eq(&Error::from_code(OsCode::Errno(6))));
+    let no_code = Error::from_info(error_info!("no code"));
+    assert!(!no_code.code_ptr_eq(&no_code));
+}
+
+#[test]
+fn code_matches_any() {
+    let codes = [
+        Error::from_code(Code1::A).code().unwrap(),
+        Error::from_code(Code2::X).code().unwrap(),
+    ];
+
+    assert!(Error::from_code(Code1::A).code_matches_any(&codes));
+    assert!(Error::from_info(error_info!(Code2::X, "with a message")).code_matches_any(&codes));
+    assert!(!Error::from_code(Code1::B).code_matches_any(&codes));
+    assert!(!Error::from_info(error_info!("no code")).code_matches_any(&codes));
+    assert!(!Error::from_code(Code1::A).code_matches_any(&[]));
+}
+
+fn grouped_error() -> Error {
+    Error::from_code(Code1::A).with_context(error_info!(Code2::X, "loading failed"))
+}
+
+#[test]
+fn grouping_key() {
+    let a = grouped_error();
+    let b = Error::from_code(Code1::A).with_context(error_info!(Code2::X, "loading failed"));
+    assert_eq!(a.grouping_key(), b.grouping_key());
+    assert_eq!(a.grouping_key(), grouped_error().grouping_key());
+
+    let different_message =
+        Error::from_code(Code1::A).with_context(error_info!(Code2::X, "saving failed"));
+    assert_ne!(a.grouping_key(), different_message.grouping_key());
+
+    let different_code =
+        Error::from_code(Code1::B).with_context(error_info!(Code2::X, "loading failed"));
+    assert_ne!(a.grouping_key(), different_code.grouping_key());
+}
+
+fn fetch_error(url: &str) -> Error {
+    Error::from_code(Code1::A).with_context(error_info!(Code2::X, "fetching {url} failed"))
+}
+
+#[test]
+fn fingerprint() {
+    let a = fetch_error(std::hint::black_box("https://example.com/a"));
+    let b = fetch_error(std::hint::black_box("https://example.com/b"));
+    assert_eq!(a.fingerprint(), b.fingerprint());
+
+    // the same code path created at a different location
+    let moved =
+        Error::from_code(Code1::A).with_context(error_info!(Code2::X, "fetching {} failed", 1));
+    assert_eq!(a.fingerprint(), moved.fingerprint());
+
+    let different_code =
+        Error::from_code(Code1::B).with_context(error_info!(Code2::X, "fetching failed"));
+    assert_ne!(a.fingerprint(), different_code.fingerprint());
+    let different_order =
+        Error::from_code(Code2::X).with_context(error_info!(Code1::A, "fetching failed"));
+    assert_ne!(a.fingerprint(), different_order.fingerprint());
+
+    assert_eq!(
+        Error::from_type("ForeignError").fingerprint(),
+        Error::from_type("ForeignError").fingerprint()
+    );
+    assert_ne!(
+        Error::from_type("ForeignError").fingerprint(),
+        Error::from_type("OtherError").fingerprint()
+    );
+}
+
+#[test]
+fn root_code() {
+    let err = Error::from_code(Code1::A)
+        .with_context(error_info!("context"))
+        .with_context(error_info!(Code2::X, "relabeled"));
+    assert!(err.is(Code2::X));
+    assert!(err.root_code().unwrap().is_value(Code1::A));
+    assert!(err.code().unwrap().is_value(Code2::X));
+
+    let err = Error::from_info(error_info!("no code")).with_context(error_info!(Code2::Y, "outer"));
+    assert!(err.root_code().unwrap().is_value(Code2::Y));
+
+    let err = Error::from_info(error_info!("no code"));
+    assert!(err.root_code().is_none());
+}
+
+const CODE1_A: &ErrorCodeInfo = Code1::A.const_info();
+static CODE_TABLE: [&ErrorCodeInfo; 3] =
+    [Code1::A.const_info(), Code1::B.const_info(), Code2::X.const_info()];
+const OLD_DEPRECATION: Option<&str> = EvolvingCode::Old.const_info().deprecation();
+
+#[test]
+fn const_info() {
+    assert!(core::ptr::eq(Error::from_code(Code1::A).code().unwrap(), CODE1_A));
+    for (info, code) in CODE_TABLE.iter().zip([Code1::A, Code1::B]) {
+        assert!(info.is_value(code));
+    }
+    assert!(CODE_TABLE[2].is_value(Code2::X));
+    assert_eq!(OLD_DEPRECATION, Some("use EvolvingCode::Current instead"));
+}
+
+#[test]
+fn aliases() {
+    use errcode::__macro_export::ErrorCodePrivate;
+
+    assert_eq!(AliasedCode::from_value(1001), AliasedCode::NotFound);
+    assert_eq!(AliasedCode::from_value(2001), AliasedCode::NotFound);
+    assert_eq!(AliasedCode::from_value(1002), AliasedCode::Forbidden);
+    assert_eq!(AliasedCode::from_value(-1i64 as u64), AliasedCode::Forbidden);
+    assert!(AliasedCode::NotFound.is_value(1001));
+    assert!(!AliasedCode::Forbidden.is_value(1001));
+
+    let info = AliasedCode::NotFound.const_info();
+    assert_eq!(info.value(), 2001);
+    assert_eq!(info.decode_value::<AliasedCode>(), Some(AliasedCode::NotFound));
+}
+
+#[test]
+fn code_info_formatting() {
+    let info = Code1::A.const_info();
+    assert_eq!(info.to_string(), "Code1::A");
+    let debug = format!("{info:?}");
+    assert!(debug.contains("Code1::A"), "Debug: {debug}");
+    assert!(debug.contains("value: 0"), "Debug: {debug}");
+
+    let mut info = ErrorCodeInfo::new(core::any::TypeId::of::<Code1>(), "Code1", "C", -3);
+    info.message = Some("something broke");
+    assert_eq!(info.to_string(), "Code1::C (something broke)");
+    let debug = format!("{info:?}");
+    assert!(debug.contains("value: -3"), "Debug: {debug}");
+    assert!(debug.contains("something broke"), "Debug: {debug}");
+}
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+#[code(doc_url = "https://docs.example.com/errors/E{value}-{variant}")]
+pub enum DocumentedCode {
+    NotFound = 404,
+    #[code(doc_url = "https://docs.example.com/teapot")]
+    Teapot = 418,
+}
+
+#[test]
+fn doc_url() {
+    let error = Error::from_code(DocumentedCode::NotFound);
+    assert_eq!(
+        error.doc_url().as_deref(),
+        Some("https://docs.example.com/errors/E404-NotFound")
+    );
+    let error = Error::from_code(DocumentedCode::Teapot);
+    assert_eq!(error.doc_url().as_deref(), Some("https://docs.example.com/teapot"));
+
+    assert_eq!(Error::from_code(Code1::A).doc_url(), None);
+    assert_eq!(Error::from_info(error_info!("no code")).doc_url(), None);
+}
+
+#[test]
+fn caused_by_code() {
+    let error = Error::from_code(Code1::B)
+        .with_context(error_info!("while starting"))
+        .with_context(error_info!(Code2::X, "startup failed"));
+
+    assert!(error.is(Code2::X));
+    assert!(!error.is(Code1::B));
+    assert!(error.caused_by_code(Code1::B));
+    assert!(error.caused_by_code(Code2::X));
+    assert!(!error.caused_by_code(Code1::A));
+}
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ServiceCode {
+    DiskFull,
+    StartupFailed,
+}
+
+#[test]
+fn depth_first_codes() {
+    let error = Error::from_code(ServiceCode::DiskFull)
+        .with_context(error_info!("while opening the journal"))
+        .with_context(error_info!(ServiceCode::StartupFailed, "startup failed"));
+    let codes: Vec<_> = error
+        .depth_first_codes()
+        .map(|x| x.decode_value::<ServiceCode>().unwrap())
+        .collect();
+    assert_eq!(codes, [ServiceCode::DiskFull, ServiceCode::StartupFailed]);
+
+    assert_eq!(
+        Error::from_info(error_info!("no code"))
+            .depth_first_codes()
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn code_first_matching() {
+    let error = Error::from_code(Code1::B).with_context(error_info!(Code2::X, "startup failed"));
+
+    assert!(Code2::X.matches(&error));
+    assert!(!Code1::B.matches(&error));
+    assert!(Code1::B.caused(&error));
+    assert!(Code2::X.caused(&error));
+    assert!(!Code1::A.caused(&error));
+    assert!(!Code1::A.matches(&Error::from_info(error_info!("no code"))));
+}
+
+#[test]
+fn is_code_or_cause() {
+    let error = Error::from_code(Code1::B)
+        .with_context(error_info!("while starting"))
+        .with_context(error_info!(Code2::X, "startup failed"));
+
+    // the current code
+    assert!(error.is_code_or_cause(Code2::X));
+    // only in the chain of causes
+    assert!(!error.is(Code1::B));
+    assert!(error.is_code_or_cause(Code1::B));
+    // absent
+    assert!(!error.is_code_or_cause(Code1::A));
+    assert!(!error.is_code_or_cause(Code2::Y));
+    assert!(!Error::from_info(error_info!("no code")).is_code_or_cause(Code1::A));
+}
+
+#[test]
+fn code_or() {
+    assert_eq!(Error::from_info(error_info!("no code")).code_or(Code1::B), Code1::B);
+    assert_eq!(Error::from_code(Code2::X).code_or(Code1::B), Code1::B);
+    assert_eq!(Error::from_code(Code1::A).code_or(Code1::B), Code1::A);
+}
+
+#[test]
+fn transparent_code() {
+    let error = Error::from_code(OsCode::Errno(2));
+    assert_eq!(error.code().unwrap().value(), 2);
+    assert_eq!(error.code().unwrap().variant_name, "Errno");
+    assert_eq!(error.downcast_code::<OsCode>(), Some(OsCode::Errno(2)));
+    assert!(error.is(OsCode::Errno(2)));
+    assert!(!error.is(OsCode::Errno(3)));
+
+    // the same payload reuses the same interned info
+    let again = Error::from_code(OsCode::Errno(2));
+    assert!(core::ptr::eq(error.code().unwrap(), again.code().unwrap()));
+
+    let negative = Error::from_code(OsCode::Errno(-4));
+    assert_eq!(negative.code().unwrap().value_i64(), Some(-4));
+    assert_eq!(negative.downcast_code::<OsCode>(), Some(OsCode::Errno(-4)));
+
+    let unit = Error::from_code(OsCode::Unknown);
+    assert_eq!(unit.code().unwrap().value(), 1_000_000);
+    assert_eq!(unit.downcast_code::<OsCode>(), Some(OsCode::Unknown));
+
+    let info = OsCode::Errno(7).const_info();
+    assert_eq!(info.variant_name, "Errno");
+    assert_eq!(info.value(), 1_000_001);
+}
+
+#[test]
+fn transparent_code_same_value_as_unit() {
+    // `Success` has the value 0, but a payload of 0 is still the transparent variant
+    let error = Error::from_code(ExitCode::Status(0));
+    assert!(error.is(ExitCode::Status(0)));
+    assert!(!error.is(ExitCode::Success));
+    assert_eq!(error.downcast_code::<ExitCode>(), Some(ExitCode::Status(0)));
+    assert_eq!(error.as_code_enum::<ExitCode>(), Ok(ExitCode::Status(0)));
+    assert!(!error.same_code_as(&Error::from_code(ExitCode::Success)));
+
+    let unit = Error::from_code(ExitCode::Success);
+    assert!(unit.is(ExitCode::Success));
+    assert!(!unit.is(ExitCode::Status(0)));
+    assert_eq!(unit.downcast_code::<ExitCode>(), Some(ExitCode::Success));
+}
+
+#[test]
+fn transparent_code_payload_limit() {
+    for port in 0..errcode::MAX_CODE_PAYLOADS as u16 {
+        Error::from_code(PortCode::Port(1000 + port));
+    }
+
+    // payloads that were interned keep their value
+    let error = Error::from_code(PortCode::Port(1000));
+    assert_eq!(error.downcast_code::<PortCode>(), Some(PortCode::Port(1000)));
+
+    // further payloads share the info of the variant, with its discriminant as the value
+    let error = Error::from_code(PortCode::Port(9000));
+    assert_eq!(error.code().unwrap().value(), 1);
+    assert_eq!(error.downcast_code::<PortCode>(), Some(PortCode::Port(1)));
+    assert!(core::ptr::eq(error.code().unwrap(), PortCode::Port(0).const_info()));
+}
+
+#[test]
+#[should_panic(expected = "unknown value")]
+fn transparent_code_value_out_of_range() {
+    let info = ErrorCodeInfo::new(core::any::TypeId::of::<PortCode>(), "PortCode", "Port", 70_000);
+    info.decode_value::<PortCode>();
+}
+
+mod storage {
+    use errcode::ErrorCode;
+
+    #[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+    #[code(group = "storage")]
+    pub enum DiskCode {
+        Full,
+        #[code(group = "hardware")]
+        Failed,
+    }
+}
+
+mod cache {
+    use errcode::ErrorCode;
+
+    #[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum CacheCode {
+        #[code(group = "storage")]
+        Evicted,
+        Miss,
+    }
+}
+
+#[test]
+fn code_group() {
+    let disk = Error::from_code(storage::DiskCode::Full);
+    let cache = Error::from_code(cache::CacheCode::Evicted);
+    assert_eq!(disk.code_group(), Some("storage"));
+    assert_eq!(disk.code_group(), cache.code_group());
+
+    assert_eq!(Error::from_code(storage::DiskCode::Failed).code_group(), Some("hardware"));
+    assert_eq!(Error::from_code(cache::CacheCode::Miss).code_group(), None);
+    assert_eq!(Error::from_info(error_info!("no code")).code_group(), None);
+}
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+#[code(meta(retryable = "false", owner = "storage-team"))]
+enum MetaCode {
+    Full,
+    #[code(meta(retryable = "true"), meta(http_status = "503"))]
+    Busy,
+}
+
+#[test]
+fn code_metadata() {
+    let full = MetaCode::Full.const_info();
+    assert_eq!(full.meta("retryable"), Some("false"));
+    assert_eq!(full.meta("owner"), Some("storage-team"));
+    assert_eq!(full.meta("http_status"), None);
+
+    let busy = Error::from_code(MetaCode::Busy);
+    let busy = busy.code().unwrap();
+    assert_eq!(busy.meta("retryable"), Some("true"));
+    assert_eq!(busy.meta("owner"), Some("storage-team"));
+    assert_eq!(busy.meta("http_status"), Some("503"));
+    assert_eq!(busy.metadata.len(), 3);
+
+    assert_eq!(Code1::A.const_info().meta("retryable"), None);
+}
+
+#[test]
+fn attach_raw_code() {
+    let error = Error::from_info(error_info!("upstream failed")).attach_raw_code(
+        "RemoteCode",
+        503,
+        Some("service unavailable"),
+    );
+    let code = error.code().unwrap();
+    assert_eq!(code.type_name, "RemoteCode");
+    assert_eq!(code.value(), 503);
+    assert_eq!(code.message, Some("service unavailable"));
+    assert_eq!(code.to_string(), "RemoteCode::503 (service unavailable)");
+    assert_eq!(error.downcast_code::<Code1>(), None);
+
+    // the same raw code is only created once
+    let again = Error::from_info(error_info!("upstream failed")).attach_raw_code(
+        "RemoteCode",
+        503,
+        Some("service unavailable"),
+    );
+    assert!(again.code_ptr_eq(&error));
+
+    let negative = Error::from_info(error_info!("upstream failed")).attach_raw_code(
+        "RemoteCode",
+        -32600,
+        None,
+    );
+    assert!(!negative.code_ptr_eq(&error));
+    assert_eq!(negative.code().unwrap().value_i64(), Some(-32600));
+    assert_eq!(negative.code_value(), Some(-32600i32 as u32));
+    assert_eq!(negative.code().unwrap().to_string(), "RemoteCode::-32600");
+}
+
+#[test]
+fn freeze_code() {
+    let mut error = Error::from_info(error_info!(Code1::A, "root cause"))
+        .with_context(error_info!(Code2::X, "while loading"));
+    error.freeze_code();
+    let error = error
+        .with_context(error_info!(Code1::B, "relabeled"))
+        .with_context(error_info!("while starting"));
+
+    assert!(error.is(Code2::X));
+    assert_eq!(error.root_code().unwrap().variant_name, "A");
+    let out = error.to_string();
+    #[cfg(feature = "repr_full")]
+    {
+        assert!(out.starts_with("while starting"), "Output: {out}");
+        assert!(out.contains("relabeled"), "Output: {out}");
+    }
+    #[cfg(not(feature = "repr_full"))]
+    assert!(out.contains("<some frames have been omitted>"), "Output: {out}");
+}
+
+#[test]
+fn take_code() {
+    let mut error = Error::from_info(error_info!(Code1::A, "root cause"))
+        .with_context(error_info!("while loading"));
+    let code = error.take_code().unwrap();
+    assert!(code.is_value(Code1::A));
+    assert!(error.code().is_none());
+    assert!(error.take_code().is_none());
+    assert!(error.root_code().unwrap().is_value(Code1::A));
+
+    let error = error.with_context(error_info!("while starting"));
+    assert!(error.code().is_none());
+    let error = error.with_context(error_info!(Code2::Y, "restamped"));
+    assert!(error.is(Code2::Y));
+
+    let mut frozen = Error::from_code(Code1::B);
+    frozen.freeze_code();
+    assert!(frozen.take_code().unwrap().is_value(Code1::B));
+    let frozen = frozen.with_context(error_info!(Code1::A, "restamped"));
+    assert!(frozen.is(Code1::A));
+}
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+#[code(severity = "error")]
+pub enum SeverityCode {
+    #[code(severity = "info")]
+    Retrying,
+    #[code(severity = "warning")]
+    Degraded,
+    Failed,
+    #[code(severity = "fatal")]
+    Corrupted,
+}
+
+#[test]
+fn severity() {
+    use errcode::Severity;
+
+    let retrying = Error::from_code(SeverityCode::Retrying);
+    assert_eq!(retrying.severity(), Some(Severity::Info));
+    assert!(!retrying.is_fatal() && !retrying.is_warning());
+
+    let degraded = Error::from_code(SeverityCode::Degraded);
+    assert_eq!(degraded.severity(), Some(Severity::Warning));
+    assert!(degraded.is_warning() && !degraded.is_fatal());
+
+    let failed = Error::from_code(SeverityCode::Failed);
+    assert_eq!(failed.severity(), Some(Severity::Error));
+    assert!(!failed.is_fatal() && !failed.is_warning());
+
+    let corrupted = Error::from_code(SeverityCode::Corrupted);
+    assert_eq!(corrupted.severity(), Some(Severity::Fatal));
+    assert!(corrupted.is_fatal() && !corrupted.is_warning());
+
+    let unset = Error::from_code(Code1::A);
+    assert_eq!(unset.severity(), None);
+    assert!(!unset.is_fatal() && !unset.is_warning());
+
+    assert!(Severity::Info < Severity::Warning && Severity::Error < Severity::Fatal);
+}
+
+#[test]
+fn max_severity() {
+    use errcode::Severity;
+
+    let relabeled = Error::from_code(SeverityCode::Corrupted)
+        .with_context(error_info!("while loading cache"))
+        .with_context_code(SeverityCode::Degraded);
+    assert_eq!(relabeled.severity(), Some(Severity::Warning));
+    assert_eq!(relabeled.max_severity(), Some(Severity::Fatal));
+    assert_eq!(relabeled.severity_or(Severity::Error), Severity::Warning);
+
+    let escalated =
+        Error::from_code(SeverityCode::Retrying).with_context_code(SeverityCode::Failed);
+    assert_eq!(escalated.max_severity(), Some(Severity::Error));
+
+    let unset = Error::from_code(Code1::A).with_context(error_info!("no severity"));
+    assert_eq!(unset.max_severity(), None);
+    assert_eq!(unset.severity_or(Severity::Error), Severity::Error);
+}
+
+fn shared_source() -> ErrorInfo<'static> {
+    error_info!(Code1::B, "shared source")
+}
+
+#[test]
+fn shared_source_address() {
+    // both frames are created from the same call site, so they share one static source
+    let err = Error::from_info(shared_source()).with_context(shared_source());
+    assert!(err.is(Code1::B));
+    assert!(err.same_code_as(&Error::from_code(Code1::B)));
+    assert!(err.root_code().unwrap().is_value(Code1::B));
+    for frame in err.frames().real() {
+        assert_eq!(frame.message(), Some("shared source"));
+    }
+
+    let err = err.with_context(error_info!("outer"));
+    assert!(err.is(Code1::B));
+}
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+#[code(group = "storage", doc_url = "https://example.com/errors/{value}")]
+enum ExplainCode {
+    #[code(severity = "fatal", meta(http_status = "507", retryable = "false"))]
+    Full = 28,
+    Other,
+}
+
+#[test]
+fn explain() {
+    let mut out = String::new();
+    ExplainCode::Full.const_info().explain(&mut out).unwrap();
+    assert_eq!(
+        out,
+        "ExplainCode::Full (value 28)\n\
+         severity: fatal\n\
+         group: storage\n\
+         docs: https://example.com/errors/28\n\
+         http_status: 507\n\
+         retryable: false"
+    );
+
+    let mut out = String::new();
+    ExplainCode::Other.const_info().explain(&mut out).unwrap();
+    assert_eq!(
+        out,
+        "ExplainCode::Other (value 29)\ngroup: storage\ndocs: https://example.com/errors/29"
+    );
+
+    let mut info =
+        ErrorCodeInfo::new(core::any::TypeId::of::<ExplainCode>(), "ExplainCode", "Legacy", -1);
+    info.message = Some("legacy failure");
+    info.deprecated = Some("use ExplainCode::Other");
+    let mut out = String::new();
+    info.explain(&mut out).unwrap();
+    assert_eq!(
+        out,
+        "ExplainCode::Legacy (value -1)\n\
+         message: legacy failure\n\
+         deprecated: use ExplainCode::Other"
+    );
+}
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u32)]
+#[non_exhaustive]
+#[code(unknown_variant = Unknown)]
+pub enum ProtocolCode {
+    Timeout = 1,
+    Refused = 2,
+    Unknown(u32),
+}
+
+#[test]
+fn unknown_variant() {
+    let info =
+        ErrorCodeInfo::new(core::any::TypeId::of::<ProtocolCode>(), "ProtocolCode", "Unknown", 999);
+    assert_eq!(info.decode_value::<ProtocolCode>(), Some(ProtocolCode::Unknown(999)));
+
+    let error = Error::from_code(ProtocolCode::Unknown(999));
+    assert_eq!(error.code().unwrap().value(), 999);
+    assert_eq!(error.downcast_code::<ProtocolCode>(), Some(ProtocolCode::Unknown(999)));
+    assert!(error.is(ProtocolCode::Unknown(999)));
+
+    // known values always decode to their own variant
+    let error = Error::from_code(ProtocolCode::Unknown(2));
+    assert_eq!(error.downcast_code::<ProtocolCode>(), Some(ProtocolCode::Refused));
+    assert_eq!(
+        Error::from_code(ProtocolCode::Timeout).downcast_code::<ProtocolCode>(),
+        Some(ProtocolCode::Timeout)
+    );
+}
@@ -0,0 +1,64 @@
+use errcode::{Error, ErrorCode, Errors, error_info};
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FieldCode {
+    Missing,
+    Invalid,
+}
+
+fn validate(fields: &[Option<i32>]) -> Result<i32, Errors> {
+    let mut errors = Errors::new();
+    let mut sum = 0;
+    for field in fields {
+        match field {
+            Some(x) if *x < 0 => errors.push(Error::from_code(FieldCode::Invalid)),
+            Some(x) => sum += x,
+            None => errors.push(Error::from_code(FieldCode::Missing)),
+        }
+    }
+    errors.into_result(sum)
+}
+
+#[test]
+fn empty_errors() {
+    assert_eq!(validate(&[Some(1), Some(2)]).unwrap(), 3);
+    assert!(Errors::default().is_empty());
+    assert_eq!(Errors::default().to_string(), "");
+}
+
+#[test]
+fn collected_errors() {
+    let errors = validate(&[None, Some(1), Some(-1)]).unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(errors.iter().next().unwrap().is(FieldCode::Missing));
+
+    let out = errors.to_string();
+    let lines: Vec<_> = out.lines().filter(|x| !x.starts_with(' ')).collect();
+    // code-only errors are shown as their humanized variant name with `humanize_codes`
+    let (missing, invalid) = if cfg!(feature = "humanize_codes") {
+        ("1. missing (FieldCode::Missing)", "2. invalid (FieldCode::Invalid)")
+    } else {
+        ("1. FieldCode::Missing", "2. FieldCode::Invalid")
+    };
+    assert!(lines[0].starts_with(missing), "Output: {out}");
+    assert!(lines[1].starts_with(invalid), "Output: {out}");
+
+    let error = Error::from(errors);
+    #[cfg(all(feature = "repr_full", not(feature = "heapless")))]
+    assert!(error.to_string().contains(invalid), "Error: {error}");
+    // the message is truncated to its first error
+    #[cfg(feature = "heapless")]
+    assert!(error.to_string().starts_with(missing), "Error: {error}");
+    #[cfg(not(feature = "repr_full"))]
+    assert!(error.to_string().contains("Errors"), "Error: {error}");
+}
+
+#[test]
+fn extend_errors() {
+    let mut errors: Errors = (0..2)
+        .map(|i| Error::from_info(error_info!("error {}", i)))
+        .collect();
+    errors.extend([Error::from_code(FieldCode::Missing)]);
+    assert_eq!(errors.len(), 3);
+    assert_eq!(errors.into_iter().count(), 3);
+}
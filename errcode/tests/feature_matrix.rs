@@ -0,0 +1,73 @@
+//! Checks that the repr feature flags are validated correctly, by running `cargo check` on the
+//! crate with each combination of them.
+
+use std::process::Command;
+
+const REPRS: &[&str] = &["repr_full", "repr_unboxed", "repr_unboxed_location", "compact_location"];
+
+/// Runs `cargo check` with the given features, returning the compiler output on failure.
+fn check(features: &[&str]) -> Result<(), String> {
+    let output = Command::new(env!("CARGO"))
+        .arg("check")
+        .arg("--quiet")
+        .arg("--manifest-path")
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+        .arg("--target-dir")
+        .arg(concat!(env!("CARGO_TARGET_TMPDIR"), "/feature_matrix"))
+        .arg("--no-default-features")
+        .arg("--features")
+        .arg(features.join(","))
+        .output()
+        .expect("could not run cargo");
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).into_owned())
+    }
+}
+
+#[test]
+fn default_repr() {
+    #[cfg(not(any(
+        feature = "repr_full",
+        feature = "repr_unboxed_location",
+        feature = "compact_location"
+    )))]
+    assert_eq!(errcode::REPR_NAME, "repr_unboxed");
+    check(&[]).unwrap();
+}
+
+#[test]
+fn single_repr_features() {
+    for feature in REPRS {
+        if let Err(output) = check(&[feature]) {
+            panic!("`{feature}` failed to compile:\n{output}");
+        }
+    }
+}
+
+#[test]
+fn conflicting_repr_features() {
+    for (i, a) in REPRS.iter().enumerate() {
+        for b in &REPRS[i + 1..] {
+            let expected = match (*a, *b) {
+                ("repr_unboxed", "compact_location") => None,
+                (_, "compact_location") => {
+                    Some("`compact_location` may only be used with `repr_unboxed`.")
+                }
+                _ => Some(
+                    "You may only use one of `repr_full`, `repr_unboxed` or \
+                     `repr_unboxed_location`.",
+                ),
+            };
+            match (check(&[a, b]), expected) {
+                (Ok(()), None) => {}
+                (Err(output), Some(message)) => {
+                    assert!(output.contains(message), "`{a}` + `{b}` gave:\n{output}")
+                }
+                (Ok(()), Some(_)) => panic!("`{a}` + `{b}` compiled successfully"),
+                (Err(output), None) => panic!("`{a}` + `{b}` failed to compile:\n{output}"),
+            }
+        }
+    }
+}
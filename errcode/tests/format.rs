@@ -0,0 +1,314 @@
+use errcode::{DisplayOpts, Error, ErrorCode, TraceStyle, error_info};
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TestCode {
+    A,
+    B,
+}
+
+fn test_error() -> Error {
+    Error::from_info(error_info!(TestCode::B, "root cause"))
+        .with_context(error_info!(TestCode::A, "top level"))
+}
+
+fn format(error: &Error, style: TraceStyle) -> String {
+    let mut out = String::new();
+    error.format_with(&mut out, style).unwrap();
+    out
+}
+
+#[test]
+fn style_one_line() {
+    let out = format(&test_error(), TraceStyle::OneLine);
+    assert!(!out.contains('\n'), "Line: {out}");
+    assert!(out.starts_with("top level (TestCode::A)"), "Line: {out}");
+    assert!(out.contains(": root cause (TestCode::B)"), "Line: {out}");
+}
+
+#[test]
+fn style_multi_line() {
+    let error = test_error();
+    let out = format(&error, TraceStyle::MultiLine);
+    assert_eq!(out, error.to_string());
+
+    let lines: Vec<_> = out.lines().collect();
+    assert_eq!(lines.len(), 2, "Line: {out}");
+    assert!(lines[0].starts_with("top level (TestCode::A)"), "Line: {out}");
+    assert!(lines[1].starts_with("    caused by: root cause (TestCode::B)"), "Line: {out}");
+}
+
+#[test]
+fn style_tree() {
+    let out = format(&test_error(), TraceStyle::Tree);
+    let lines: Vec<_> = out.lines().collect();
+    assert_eq!(lines.len(), 2, "Line: {out}");
+    assert!(lines[0].starts_with("top level (TestCode::A)"), "Line: {out}");
+    assert!(lines[1].starts_with("└─ root cause (TestCode::B)"), "Line: {out}");
+
+    let error = test_error().with_context(error_info!(TestCode::A, "outermost"));
+    let out = format(&error, TraceStyle::Tree);
+    let lines: Vec<_> = out.lines().collect();
+    assert!(lines.len() >= 3, "Line: {out}");
+    assert!(lines[0].starts_with("outermost"), "Line: {out}");
+    for (i, line) in lines.iter().enumerate().skip(1) {
+        let prefix = format!("{}└─ ", "   ".repeat(i - 1));
+        assert!(line.starts_with(&prefix), "Line {i}: {out}");
+    }
+}
+
+#[test]
+fn style_codes_only() {
+    let out = format(&test_error(), TraceStyle::CodesOnly);
+    assert_eq!(out, "TestCode::A, TestCode::B");
+    assert_eq!(test_error().codes_only().to_string(), out);
+}
+
+#[test]
+fn style_debug() {
+    let error = test_error();
+    let out = format(&error, TraceStyle::Debug);
+    assert_eq!(out, format!("{error:?}"));
+    assert!(out.starts_with('[') && out.ends_with(']'), "Line: {out}");
+    let frames: Vec<_> = error.frames().map(|x| format!("{x:?}")).collect();
+    assert_eq!(out, format!("[{}]", frames.join(", ")));
+}
+
+#[test]
+fn write_syslog() {
+    let error = test_error();
+    let mut out = String::new();
+    error.write_syslog(&mut out).unwrap();
+    assert!(!out.contains('\n'), "Line: {out}");
+    assert_eq!(out, format(&error, TraceStyle::OneLine));
+}
+
+#[test]
+fn single_frame_display() {
+    let errors = [
+        Error::from_info(error_info!("only frame")),
+        Error::from_info(error_info!("only frame: {}", 1)),
+        Error::from_code(TestCode::A),
+        Error::from_type("std::io::Error"),
+    ];
+    for error in errors {
+        let general = format(&error, TraceStyle::MultiLine);
+        if error.frame_count() == 1 {
+            assert!(!general.contains('\n'), "Line: {general}");
+        }
+        assert_eq!(error.to_string(), general);
+    }
+
+    assert_eq!(Error::from_info(error_info!("only frame")).frame_count(), 1);
+    assert_eq!(test_error().frame_count(), 2);
+}
+
+#[test]
+fn map_frames() {
+    let error = Error::from_info(error_info!(TestCode::A, "disk {} full", 1))
+        .with_context(error_info!("while saving"));
+    let mapped = error.clone().map_frames(|x| x.to_uppercase());
+
+    assert!(mapped.is(TestCode::A));
+    assert_eq!(mapped.root_code().unwrap().variant_name, "A");
+    assert_eq!(mapped.frame_count(), error.frame_count());
+    assert_eq!(
+        mapped.source_location().map(|x| x.line),
+        error.source_location().map(|x| x.line)
+    );
+
+    let out = mapped.to_string();
+    #[cfg(feature = "repr_full")]
+    {
+        assert!(out.contains("DISK 1 FULL (TestCode::A)"), "Output: {out}");
+        assert!(out.contains("WHILE SAVING"), "Output: {out}");
+    }
+    #[cfg(not(feature = "repr_full"))]
+    assert_eq!(out, error.to_string());
+}
+
+#[test]
+fn display_chain() {
+    let error = Error::from_info(error_info!("disk full"))
+        .with_context(error_info!("while saving"))
+        .with_context(error_info!("request failed"));
+    let out = error.display_chain(" | ").to_string();
+    let parts: Vec<_> = out.split(" | ").collect();
+
+    assert!(parts[0].starts_with("request failed"), "Output: {out}");
+    assert!(parts.last().unwrap().starts_with("disk full"), "Output: {out}");
+    assert!(!out.contains("<some frames have been omitted>"), "Output: {out}");
+    assert!(!out.contains('\n'), "Output: {out}");
+}
+
+#[test]
+fn display_rev() {
+    let error = Error::from_info(error_info!("disk full"))
+        .with_context(error_info!("while saving"))
+        .with_context(error_info!("request failed"));
+    let forward = error.display_chain(" | ").to_string();
+    let reverse = error.display_rev(" | ").to_string();
+
+    assert!(reverse.starts_with("disk full"), "Output: {reverse}");
+    let mut parts: Vec<_> = forward.split(" | ").collect();
+    parts.reverse();
+    assert_eq!(parts.join(" | "), reverse);
+}
+
+#[test]
+fn display_clean() {
+    let error = Error::from_info(error_info!(TestCode::A, "the disk is full"))
+        .with_context(error_info!(TestCode::B))
+        .with_context(error_info!("could not save the file"));
+
+    let display = error.to_string();
+    assert!(display.contains("(TestCode::A)"), "Display: {display}");
+    assert!(display.contains("[at "), "Display: {display}");
+
+    let clean = error.display_clean().to_string();
+    #[cfg(feature = "repr_full")]
+    assert_eq!(clean, "could not save the file: the disk is full");
+    assert!(clean.ends_with("the disk is full"), "Clean: {clean}");
+    assert!(!clean.contains("TestCode") && !clean.contains("[at "), "Clean: {clean}");
+}
+
+#[test]
+fn replace_message() {
+    let internal = || {
+        Error::from_info(error_info!("root cause"))
+            .with_context(error_info!(TestCode::A, "internal message"))
+    };
+
+    // static messages are replaced in place by every representation
+    let mut error = internal();
+    assert!(error.replace_message("something went wrong"));
+    let out = error.to_string();
+    let first_line = out.lines().next().unwrap();
+    assert!(first_line.starts_with("something went wrong (TestCode::A)"), "Output: {out}");
+    assert!(!out.contains("internal message"), "Output: {out}");
+    assert!(out.contains("root cause"), "Output: {out}");
+    assert!(error.is(TestCode::A));
+    assert_eq!(error.frame_count(), internal().frame_count());
+
+    // messages created at runtime are only stored by `repr_full`
+    let mut error = internal();
+    let replaced = error.replace_message(format!("something went {}", "wrong"));
+    assert_eq!(replaced, cfg!(feature = "repr_full"));
+    let out = error.to_string();
+    let first_line = out.lines().next().unwrap();
+    if replaced {
+        assert!(first_line.starts_with("something went wrong"), "Output: {out}");
+    } else {
+        assert!(first_line.starts_with("internal message"), "Output: {out}");
+    }
+    assert!(out.contains("root cause"), "Output: {out}");
+}
+
+#[test]
+fn display_opts() {
+    let error = test_error();
+    let opts = DisplayOpts::default();
+    assert_eq!(error.display_opts(opts).to_string(), error.to_string());
+
+    let no_codes = error
+        .display_opts(DisplayOpts { show_codes: false, ..opts })
+        .to_string();
+    assert!(!no_codes.contains("TestCode"), "Output: {no_codes}");
+    assert!(no_codes.contains("top level"), "Output: {no_codes}");
+
+    let no_locations = error
+        .display_opts(DisplayOpts { show_locations: false, ..opts })
+        .to_string();
+    assert!(!no_locations.contains("[at "), "Output: {no_locations}");
+    assert!(no_locations.starts_with("top level (TestCode::A)"), "Output: {no_locations}");
+
+    let one_line = error
+        .display_opts(DisplayOpts { multiline: false, ..opts })
+        .to_string();
+    assert!(!one_line.contains('\n'), "Output: {one_line}");
+    assert_eq!(one_line, format(&error, TraceStyle::OneLine));
+
+    let bare = DisplayOpts { show_codes: false, show_locations: false, multiline: false, ..opts };
+    #[cfg(feature = "repr_full")]
+    assert_eq!(error.display_opts(bare).to_string(), "top level: root cause");
+    assert!(
+        error
+            .display_opts(bare)
+            .to_string()
+            .starts_with("top level")
+    );
+}
+
+#[test]
+fn display_indented() {
+    struct Report {
+        error: Error,
+    }
+    impl std::fmt::Debug for Report {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Report {{\n    error: {},\n}}", self.error.display_indented(4))
+        }
+    }
+
+    let error = test_error();
+    let out = format!("{:?}", Report { error: error.clone() });
+    let lines: Vec<_> = out.lines().collect();
+    assert_eq!(lines.len(), error.frame_count() + 2, "Output: {out}");
+    assert!(lines[1].starts_with("    error: top level"), "Output: {out}");
+    for line in &lines[2..lines.len() - 1] {
+        assert!(line.starts_with("        caused by: "), "Output: {out}");
+    }
+    assert_eq!(lines[lines.len() - 1], "}");
+
+    // a single frame has nothing to indent
+    let single = Error::from_code(TestCode::A);
+    assert_eq!(single.display_indented(4).to_string(), single.to_string());
+}
+
+#[test]
+fn fmt_frame_at() {
+    let error = test_error();
+    let frames: Vec<_> = error.frames().collect();
+    for (idx, frame) in frames.iter().enumerate() {
+        let mut out = String::new();
+        assert!(error.fmt_frame_at(idx, &mut out).unwrap());
+        assert_eq!(out, frame.to_string());
+    }
+
+    let mut out = String::new();
+    assert!(!error.fmt_frame_at(frames.len(), &mut out).unwrap());
+    assert!(out.is_empty());
+}
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub enum DiskCode {
+    DiskFull,
+    IOError,
+    Quota_Exceeded,
+    Retry2Times,
+}
+
+#[test]
+fn code_only_fallback() {
+    let opts = DisplayOpts { show_locations: false, ..DisplayOpts::default() };
+    let message = |code: DiskCode| Error::from_code(code).display_opts(opts).to_string();
+
+    #[cfg(not(feature = "humanize_codes"))]
+    {
+        assert_eq!(message(DiskCode::DiskFull), "DiskCode::DiskFull");
+        assert_eq!(message(DiskCode::IOError), "DiskCode::IOError");
+    }
+    #[cfg(feature = "humanize_codes")]
+    {
+        assert_eq!(message(DiskCode::DiskFull), "disk full (DiskCode::DiskFull)");
+        assert_eq!(message(DiskCode::IOError), "io error (DiskCode::IOError)");
+        assert_eq!(message(DiskCode::Quota_Exceeded), "quota exceeded (DiskCode::Quota_Exceeded)");
+        assert_eq!(message(DiskCode::Retry2Times), "retry2 times (DiskCode::Retry2Times)");
+
+        // the code can still be hidden, leaving only the humanized name
+        let out = Error::from_code(DiskCode::DiskFull)
+            .display_opts(DisplayOpts { show_codes: false, ..opts })
+            .to_string();
+        assert_eq!(out, "disk full");
+    }
+}
@@ -0,0 +1,413 @@
+use errcode::{Error, ErrorCode, error_info};
+use std::hint::black_box;
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TestCode {
+    A,
+    B,
+}
+
+#[track_caller]
+fn from_elsewhere() -> Error {
+    Error::from_info(error_info!("root cause"))
+}
+
+fn test_errors() -> Vec<Error> {
+    vec![
+        Error::from_code(TestCode::A),
+        Error::from_info(error_info!("root cause")),
+        from_elsewhere(),
+        Error::from_type("std::io::Error"),
+        Error::from_type("std::io::Error").with_context(error_info!("context")),
+        Error::from_info(error_info!("root cause"))
+            .with_context(error_info!(TestCode::A, "intermediate 1"))
+            .with_context(error_info!("intermediate 2"))
+            .with_context(error_info!(TestCode::B, "intermediate 3"))
+            .with_context(error_info!("top level")),
+        from_elsewhere()
+            .with_context(error_info!(TestCode::A, "intermediate 1"))
+            .with_context(error_info!("intermediate 2"))
+            .with_context(error_info!("top level")),
+    ]
+}
+
+#[test]
+fn size_hint_is_exact() {
+    for error in test_errors() {
+        let mut iter = error.frames();
+        let mut remaining = error.frames().count();
+        assert_eq!(iter.len(), remaining, "Error: {error}");
+        while iter.next().is_some() {
+            remaining -= 1;
+            assert_eq!(iter.size_hint(), (remaining, Some(remaining)), "Error: {error}");
+        }
+        assert_eq!(remaining, 0);
+    }
+}
+
+#[test]
+#[cfg(feature = "repr_full")]
+fn collect_allocates_once() {
+    let error = test_errors().pop().unwrap();
+    let frames: Vec<_> = error.frames().collect();
+    assert!(frames.len() >= 4);
+    assert_eq!(frames.capacity(), frames.len());
+}
+
+#[test]
+#[cfg(feature = "repr_full")]
+fn reverse_iteration() {
+    for error in test_errors() {
+        let forward: Vec<_> = error.frames().map(|x| x.to_string()).collect();
+        let mut backward: Vec<_> = error.frames().rev().map(|x| x.to_string()).collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+
+        let deepest = error.frames().next_back().unwrap();
+        assert_eq!(&deepest.to_string(), forward.last().unwrap());
+    }
+
+    let error = Error::from_info(error_info!("root cause"))
+        .with_context(error_info!("intermediate"))
+        .with_context(error_info!("top level"));
+    let mut iter = error.frames();
+    assert!(
+        iter.next_back()
+            .unwrap()
+            .to_string()
+            .starts_with("root cause")
+    );
+    assert!(iter.next().unwrap().to_string().starts_with("top level"));
+    assert_eq!(iter.len(), 1);
+    assert!(
+        iter.next_back()
+            .unwrap()
+            .to_string()
+            .starts_with("intermediate")
+    );
+    assert!(iter.next().is_none());
+    assert!(iter.next_back().is_none());
+}
+
+#[test]
+fn into_parts() {
+    let error = Error::from_info(error_info!(TestCode::A, "disk full"))
+        .with_context(error_info!("while saving"));
+    let clean = error.display_clean().to_string();
+
+    let (code, frames) = error.into_parts();
+    assert_eq!(code.unwrap().variant_name, "A");
+    let rebuilt: Vec<_> = frames
+        .iter()
+        .filter_map(|(message, _)| message.as_deref())
+        .filter(|x| !x.starts_with('<'))
+        .collect();
+    assert_eq!(rebuilt.join(": "), clean);
+    if errcode::HAS_LOCATIONS {
+        assert!(frames[0].1.is_some());
+    }
+}
+
+#[test]
+fn frames_owned() {
+    let owned = {
+        let error = Error::from_info(error_info!(TestCode::A, "disk {} full", 1))
+            .with_context(error_info!("while saving"));
+        assert_eq!(error.frames_owned().len(), error.frame_count());
+        error.frames_owned()
+    };
+
+    let last = owned.last().unwrap();
+    assert_eq!(last.code.unwrap().variant_name, "A");
+    #[cfg(feature = "repr_full")]
+    assert_eq!(last.message.as_deref(), Some("disk 1 full"));
+    assert_eq!(owned[0].message.as_deref(), Some("while saving"));
+    assert!(owned[0].code.is_none());
+}
+
+/// The frames of an error built the same way, ignoring locations, which every representation
+/// records differently.
+fn golden_frames(error: &Error) -> Vec<(Option<String>, Option<&'static str>)> {
+    error
+        .frames_owned()
+        .into_iter()
+        .filter(|x| x.message.as_deref() != Some("<ErrorInfo constructed>"))
+        .map(|x| (x.message, x.code.map(|x| x.variant_name)))
+        .collect()
+}
+
+#[test]
+fn frame_order_is_repr_independent() {
+    let error = Error::from_info(error_info!(TestCode::A, "root cause"))
+        .with_context(error_info!(TestCode::B, "outer context"));
+    assert_eq!(
+        golden_frames(&error),
+        [
+            (Some("outer context".to_string()), Some("B")),
+            (Some("root cause".to_string()), Some("A")),
+        ]
+    );
+    assert!(error.to_string().starts_with("outer context (TestCode::B)"));
+    assert!(
+        error
+            .to_string()
+            .contains("caused by: root cause (TestCode::A)")
+    );
+}
+
+#[test]
+fn with_cause() {
+    let cause = Error::from_info(error_info!(TestCode::A, "config is invalid"))
+        .with_context(error_info!("while loading config"));
+    let error = Error::from_info(error_info!(TestCode::B, "startup failed")).with_cause(cause);
+
+    assert!(error.is(TestCode::B));
+    assert!(error.root_code().unwrap().is_value(TestCode::A));
+
+    let out = error.to_string();
+    let startup = out.find("startup failed").unwrap();
+    let config = out.find("config is invalid").unwrap();
+    assert!(startup < config, "Output: {out}");
+
+    #[cfg(feature = "repr_full")]
+    {
+        let boundary = out.find("<start of attached cause>").unwrap();
+        let loading = out.find("while loading config").unwrap();
+        assert!(startup < boundary && boundary < loading && loading < config, "Output: {out}");
+        assert_eq!(error.frames().count(), error.frame_count());
+        assert_eq!(error.frames().rev().count(), error.frame_count());
+    }
+    #[cfg(not(feature = "repr_full"))]
+    assert!(out.contains("<some frames have been omitted>"), "Output: {out}");
+}
+
+fn same_origin(context: bool) -> Error {
+    let error = Error::from_info(error_info!(TestCode::A, "root cause"));
+    if context {
+        error.with_context(error_info!(TestCode::B, "outer context"))
+    } else {
+        error.with_context(error_info!("a different context"))
+    }
+}
+
+#[test]
+fn is_same_origin() {
+    assert!(same_origin(true).is_same_origin(&same_origin(false)));
+    assert!(same_origin(true).is_same_origin(&same_origin(true)));
+
+    let other = Error::from_info(error_info!(TestCode::A, "root cause"));
+    assert!(!same_origin(true).is_same_origin(&other));
+    let other = Error::from_info(error_info!(TestCode::B, "root cause"));
+    assert!(!same_origin(true).is_same_origin(&other));
+}
+
+#[test]
+fn frames_meta() {
+    let error = Error::from_info(error_info!(TestCode::A, "root cause"))
+        .with_context(error_info!("middle context"))
+        .with_context(error_info!(TestCode::B, "outer context"));
+    let metas: Vec<_> = error.frames_meta().map(|x| x.1).collect();
+    assert_eq!(metas.len(), error.frame_count());
+
+    for (i, meta) in metas.iter().enumerate() {
+        assert_eq!(meta.depth, i);
+        assert_eq!(meta.is_first, i == 0);
+    }
+    assert_eq!(metas.iter().filter(|x| x.is_last_real).count(), 1);
+
+    let (frame, _) = error.frames_meta().find(|x| x.1.is_last_real).unwrap();
+    assert!(frame.to_string().starts_with("root cause"), "Frame: {frame}");
+
+    #[cfg(feature = "repr_full")]
+    {
+        assert_eq!(metas.len(), 3);
+        assert!(metas[2].is_last_real);
+    }
+}
+
+#[test]
+fn with_context_each() {
+    const CONTEXTS: &[&str] = &["context 1", "context 2", "context 3", "context 4", "context 5"];
+    let error = Error::from_info(error_info!("root cause")).with_context_each(CONTEXTS);
+
+    let out = error.to_string();
+    assert!(out.starts_with("context 5"), "Output: {out}");
+    assert!(out.contains("root cause"), "Output: {out}");
+    #[cfg(feature = "repr_full")]
+    {
+        assert_eq!(error.frame_count(), 6);
+        let positions: Vec<_> = (1..=5)
+            .map(|i| out.find(&format!("context {i}")).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|x| x[0] > x[1]), "Output: {out}");
+    }
+    #[cfg(not(feature = "repr_full"))]
+    assert!(out.contains("<some frames have been omitted>"), "Output: {out}");
+}
+
+#[test]
+fn context_if() {
+    let error = Error::from_info(error_info!("root cause")).context_if(true, "verbose context");
+    assert_eq!(error.frame_count(), 2);
+    assert!(error.to_string().starts_with("verbose context"));
+
+    let error = Error::from_info(error_info!("root cause")).context_if(false, "verbose context");
+    assert_eq!(error.frame_count(), 1);
+    assert!(!error.to_string().contains("verbose context"));
+}
+
+#[test]
+fn real_frames() {
+    use errcode::FrameIterExt;
+
+    let error = Error::from_type("io::Error")
+        .with_context(error_info!("while reading"))
+        .with_context(error_info!("while loading"));
+    let is_type_lost =
+        |x: &errcode::ErrorFrame| x.to_string().starts_with("<original error type lost>");
+    assert!(!error.frames().real().any(|x| is_type_lost(&x)));
+
+    #[cfg(feature = "repr_full")]
+    assert_eq!(error.frames().real().count(), error.frame_count());
+    #[cfg(not(feature = "repr_full"))]
+    {
+        assert!(error.frames().any(|x| is_type_lost(&x)));
+        assert!(error.frames().real().count() < error.frame_count());
+    }
+}
+
+#[test]
+fn context_depth_is_bounded() {
+    let mut error = Error::from_info(error_info!(TestCode::A, "root cause"));
+    for _ in 0..10000 {
+        error = error.with_context(error_info!("retrying"));
+    }
+    error = error.with_context(error_info!(TestCode::B, "gave up"));
+
+    assert!(error.frame_count() < 4096, "frame count: {}", error.frame_count());
+    assert!(error.is(TestCode::B));
+    assert_eq!(error.root_code().unwrap().variant_name, "A");
+    let out = error.to_string();
+    assert!(out.starts_with("gave up"), "Output: {}", &out[..100]);
+    assert!(out.contains("<some frames have been omitted>"));
+    assert!(out.contains("root cause"));
+}
+
+#[test]
+fn any_frame_and_find_frame() {
+    let error = Error::from_info(error_info!(TestCode::A, "connection timeout"))
+        .with_context(error_info!("while fetching"));
+
+    assert!(error.any_frame(|x| x.message().is_some_and(|x| x.contains("timeout"))));
+    assert!(!error.any_frame(|x| x.message().is_some_and(|x| x.contains("refused"))));
+
+    let coded = error.find_frame(|x| x.code().is_some()).unwrap();
+    assert_eq!(coded.code().unwrap().variant_name, "A");
+    assert!(
+        error
+            .find_frame(|x| x.code().is_some_and(|x| x.variant_name == "B"))
+            .is_none()
+    );
+}
+
+#[test]
+fn mark() {
+    let error = Error::from_info(error_info!("root cause"));
+    let line = line!() + 1;
+    let error = error.mark();
+
+    let marker = error.frames().next().unwrap();
+    assert!(marker.message().is_none() && marker.code().is_none());
+    assert_eq!(marker.location().unwrap().line, line);
+    let debug = format!("{error:?}");
+    assert!(debug.contains(&format!("line: {line}")), "Debug: {debug}");
+    let display = error.to_string();
+    let expected = format!("<reached here> [at {}:{line}:", file!());
+    assert!(display.starts_with(&expected), "Display: {display}");
+    assert!(display.contains("root cause"), "Display: {display}");
+
+    // the unboxed representations only keep the outermost context
+    let error = error.with_context(error_info!("outer"));
+    let marked = error.frames().any(|x| x.message().is_none());
+    assert_eq!(marked, cfg!(feature = "repr_full"));
+}
+
+#[test]
+fn context_full() {
+    let message = if black_box(true) {
+        "while saving"
+    } else {
+        "while loading"
+    };
+    let error = Error::from_info(error_info!("root cause")).context_full(TestCode::A, message);
+    assert!(error.is(TestCode::A));
+
+    let outer = error.frames().next().unwrap();
+    assert!(outer.code().unwrap().is_value(TestCode::A));
+    assert_eq!(outer.message(), Some("while saving"));
+    assert!(outer.to_string().starts_with("while saving (TestCode::A)"), "Frame: {outer}");
+    assert!(error.to_string().starts_with("while saving (TestCode::A)"), "Error: {error}");
+    #[cfg(feature = "repr_full")]
+    assert_eq!(error.frame_count(), 2);
+}
+
+#[test]
+fn first_with_code() {
+    let error = Error::from_info(error_info!("root cause"))
+        .with_context(error_info!(TestCode::A, "while loading the config"))
+        .with_context(error_info!("while starting"));
+
+    // the unboxed representations keep the context with a code over the outermost context
+    #[cfg(feature = "repr_full")]
+    assert!(error.frames().next().unwrap().code().is_none());
+    let frame = error.first_with_code().unwrap();
+    assert!(frame.code().unwrap().is_value(TestCode::A));
+    assert_eq!(frame.message(), Some("while loading the config"));
+
+    let error = Error::from_code(TestCode::B).with_context(error_info!("with a message"));
+    let frame = error.first_with_code().unwrap();
+    assert!(frame.code().unwrap().is_value(TestCode::B));
+    assert_ne!(frame.message(), Some("with a message"));
+
+    assert!(
+        Error::from_info(error_info!("no code"))
+            .first_with_code()
+            .is_none()
+    );
+}
+
+#[test]
+fn try_context() {
+    let mut error = Error::from_info(error_info!("root cause"));
+    assert!(error.try_context("first context"));
+    assert_eq!(error.frames().next().unwrap().message(), Some("first context"));
+    let display = error.to_string();
+    assert!(display.starts_with("first context"), "Display: {display}");
+    assert!(!display.contains("<internal error"), "Display: {display}");
+
+    // the unboxed representations keep a coded context over one without a code
+    let mut error = error.with_context(error_info!(TestCode::A, "coded context"));
+    let kept = error.try_context("plain context");
+    assert_eq!(kept, cfg!(feature = "repr_full"));
+    assert!(error.is(TestCode::A));
+    if !kept {
+        assert_eq!(error.frames().next().unwrap().message(), Some("coded context"));
+    }
+}
+
+#[test]
+fn location_threshold() {
+    let mismatch = |error: &Error| {
+        error
+            .frames()
+            .any(|x| x.to_string().starts_with("<ErrorInfo"))
+    };
+
+    let info = error_info!("two lines above");
+
+    let error = Error::from_info(info);
+    assert_eq!(mismatch(&error), errcode::HAS_LOCATIONS && errcode::LOCATION_THRESHOLD < 2);
+
+    let same_line = Error::from_info(error_info!("same line"));
+    assert!(!mismatch(&same_line));
+}
@@ -0,0 +1,45 @@
+//! Runs the seed corpus for the fuzzing entry points.
+
+use errcode::__fuzz::{build_error, run, truncate};
+
+const SEED_CORPUS: &[&[u8]] = &[
+    &[],
+    &[0],
+    &[1],
+    &[2],
+    &[0, 0],
+    &[1, 0],
+    &[2, 1, 3],
+    &[4, 1, 0, 2, 3, 1],
+    &[5, 3, 3, 3],
+    &[6, 2, 0, 0],
+    &[9, 1, 1, 2, 2, 0, 0, 3, 3],
+    &[255, 254, 253, 252, 251],
+];
+
+#[test]
+fn seed_corpus() {
+    for input in SEED_CORPUS {
+        run(input);
+        assert_eq!(build_error(input).is_some(), !input.is_empty());
+    }
+}
+
+#[test]
+fn truncate_to_nothing() {
+    let mut error = build_error(&[2, 1, 3]).unwrap();
+    truncate(&mut error, 0);
+
+    let out = error.to_string();
+    assert!(!out.is_empty());
+    #[cfg(feature = "repr_full")]
+    {
+        assert_eq!(error.frame_count(), 1);
+        assert_eq!(out, "<internal error: no message or code given???>");
+        assert!(error.code().is_none());
+        assert!(error.source_location().is_none());
+
+        let error = error.with_context(errcode::error_info!("more context"));
+        assert!(error.to_string().starts_with("more context"));
+    }
+}
@@ -0,0 +1,123 @@
+#![cfg(feature = "heapless")]
+
+use errcode::{
+    Error, ErrorCode, FrameIterExt, HEAPLESS_MESSAGE_CAPACITY, HEAPLESS_STEP_CAPACITY, error_info,
+};
+use std::hint::black_box;
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TestCode {
+    A,
+}
+
+fn messages(error: &Error) -> Vec<String> {
+    error
+        .frames()
+        .real()
+        .filter_map(|x| x.message().map(str::to_string))
+        .collect()
+}
+
+fn has_omitted_frame(error: &Error) -> bool {
+    error.frames().count() > error.frames().real().count()
+}
+
+#[test]
+fn message_truncation() {
+    let long = black_box("x").repeat(100);
+    let error = Error::from_info(error_info!("{}", long));
+    assert_eq!(
+        error.frames().next().unwrap().message(),
+        Some(&long[..HEAPLESS_MESSAGE_CAPACITY])
+    );
+
+    // truncation never splits a character
+    let wide = black_box("é").repeat(HEAPLESS_MESSAGE_CAPACITY);
+    let error = Error::from_info(error_info!("{}", wide));
+    let message = error
+        .frames()
+        .next()
+        .unwrap()
+        .message()
+        .unwrap()
+        .to_string();
+    assert_eq!(message, wide[..HEAPLESS_MESSAGE_CAPACITY]);
+
+    let short = black_box("short").to_string();
+    let error = Error::from_info(error_info!("{}", short)).map_frames(|x| x.repeat(100));
+    let message = error.frames().next().unwrap().message().unwrap().len();
+    assert_eq!(message, HEAPLESS_MESSAGE_CAPACITY);
+}
+
+#[test]
+fn context_omission() {
+    let mut error = Error::from_info(error_info!("root cause"));
+    for i in 1..HEAPLESS_STEP_CAPACITY {
+        error = error.with_context(error_info!("context {}", i));
+    }
+    assert_eq!(error.frames().real().count(), HEAPLESS_STEP_CAPACITY);
+    assert!(!has_omitted_frame(&error));
+
+    let error = error
+        .with_context(error_info!(TestCode::A, "past capacity"))
+        .with_context(error_info!("outermost"));
+    assert_eq!(error.frames().real().count(), HEAPLESS_STEP_CAPACITY);
+    assert!(has_omitted_frame(&error));
+
+    let messages = messages(&error);
+    assert_eq!(messages.first().unwrap(), "outermost");
+    assert_eq!(messages.last().unwrap(), "root cause");
+}
+
+#[derive(Debug)]
+struct ChainError {
+    depth: usize,
+    source: Option<Box<ChainError>>,
+}
+impl ChainError {
+    fn new(depth: usize) -> Self {
+        ChainError { depth, source: (depth > 0).then(|| Box::new(ChainError::new(depth - 1))) }
+    }
+}
+impl std::fmt::Display for ChainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "level {}", self.depth)
+    }
+}
+impl std::error::Error for ChainError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref().map(|x| x as _)
+    }
+}
+
+#[test]
+fn source_chain_omission() {
+    let depth = HEAPLESS_STEP_CAPACITY + 4;
+    let error = Error::from(ChainError::new(depth));
+    assert_eq!(error.frames().real().count(), HEAPLESS_STEP_CAPACITY);
+    assert!(has_omitted_frame(&error));
+
+    // the sources closest to the origin are kept
+    for (i, message) in messages(&error).iter().enumerate() {
+        assert_eq!(*message, format!("level {}", depth - i));
+    }
+
+    let error = Error::from(ChainError::new(2));
+    assert_eq!(messages(&error), ["level 2", "level 1", "level 0"]);
+    assert!(!has_omitted_frame(&error));
+}
+
+#[test]
+fn cause_omission() {
+    let mut cause = Error::from_info(error_info!(TestCode::A, "cause root"));
+    for _ in 1..HEAPLESS_STEP_CAPACITY {
+        cause = cause.with_context(error_info!("cause context"));
+    }
+    let error = Error::from_info(error_info!("effect")).with_cause(cause);
+    assert_eq!(error.frames().real().count(), HEAPLESS_STEP_CAPACITY);
+    assert!(has_omitted_frame(&error));
+
+    // the innermost steps of the cause are kept
+    assert_eq!(messages(&error).last().unwrap(), "cause root");
+    assert!(error.is(TestCode::A));
+}
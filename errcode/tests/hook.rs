@@ -0,0 +1,30 @@
+use errcode::{Error, ErrorCode, error_info};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum HookCode {
+    Counted,
+    Ignored,
+}
+
+static COUNTED: AtomicUsize = AtomicUsize::new(0);
+
+fn count_errors(error: &Error) {
+    if error.is(HookCode::Counted) {
+        COUNTED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn error_hook() {
+    errcode::set_error_hook(count_errors);
+    let _ = Error::from_code(HookCode::Counted);
+    let _ = Error::from_info(error_info!(HookCode::Counted, "with a message"));
+    let _ = Error::from_code(HookCode::Ignored);
+    let _ = Error::from_code(HookCode::Ignored).with_context(error_info!(HookCode::Counted));
+    assert_eq!(COUNTED.load(Ordering::Relaxed), 2);
+
+    errcode::remove_error_hook();
+    let _ = Error::from_code(HookCode::Counted);
+    assert_eq!(COUNTED.load(Ordering::Relaxed), 2);
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "std")]
+
+use errcode::{Error, IoCode};
+use std::io::{self, ErrorKind};
+
+#[test]
+fn io_error_kind_round_trip() {
+    for kind in [ErrorKind::NotFound, ErrorKind::PermissionDenied, ErrorKind::TimedOut] {
+        let error = Error::from(io::Error::new(kind, "something went wrong"));
+        let code = error.downcast_code::<IoCode>().unwrap();
+        assert_eq!(code.kind(), kind);
+        assert!(error.is(IoCode::from(kind)));
+    }
+}
+
+#[test]
+fn io_error_unknown_kind() {
+    let error = Error::from(io::Error::other("something went wrong"));
+    assert!(error.is(IoCode::Other));
+}
+
+#[test]
+fn io_error_message() {
+    let error = Error::from(io::Error::new(ErrorKind::NotFound, "no such file"));
+    let line = error.to_string();
+    assert!(line.contains("IoCode::NotFound"), "Line: {line}");
+
+    #[cfg(feature = "repr_full")]
+    assert!(line.starts_with("no such file (IoCode::NotFound)"), "Line: {line}");
+}
+
+#[test]
+fn io_error_question_mark() {
+    fn read() -> Result<String, Error> {
+        Ok(std::fs::read_to_string("/this/path/does/not/exist")?)
+    }
+    assert!(read().unwrap_err().is(IoCode::NotFound));
+}
+
+#[test]
+#[cfg(feature = "repr_full")]
+fn io_error_is_conversion() {
+    assert!(Error::from(io::Error::other("something went wrong")).is_conversion());
+}
@@ -0,0 +1,34 @@
+use errcode::{Error, ErrorCode, error_info};
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum JsonCode {
+    Quoted,
+}
+
+#[test]
+fn write_json() {
+    let error = Error::from_info(error_info!("line one\nline \"two\"\t\\ \u{1}"))
+        .with_context(error_info!(JsonCode::Quoted, "while parsing"));
+    let mut out = String::new();
+    error.write_json(&mut out).unwrap();
+    assert!(!out.contains('\n'), "Output: {out}");
+    assert_eq!(out, error.display_json_line().to_string());
+
+    let value: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(value["code"], "JsonCode::Quoted");
+    assert_eq!(value["message"], "while parsing");
+    let frames = value["frames"].as_array().unwrap();
+    assert_eq!(frames[0]["code"], "JsonCode::Quoted");
+    assert_eq!(frames.last().unwrap()["message"], "line one\nline \"two\"\t\\ \u{1}");
+    assert!(frames.last().unwrap()["code"].is_null());
+}
+
+#[test]
+fn write_json_without_code() {
+    let error = Error::from_info(error_info!("no code"));
+    let value: serde_json::Value =
+        serde_json::from_str(&error.display_json_line().to_string()).unwrap();
+    assert!(value["code"].is_null());
+    assert_eq!(value["message"], "no code");
+    assert_eq!(value["frames"].as_array().unwrap().len(), 1);
+}
@@ -1,4 +1,6 @@
 use errcode::{ErrorCode, error_info, error, bail, ensure, prelude::*};
+use errcode::{Error, ErrorCodeInfo};
+use errcode::__macro_export::{DecodedLocation, ErrorInfoImpl, StaticMessageInfo, new_error_info};
 
 #[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
 pub enum TestCode {
@@ -25,8 +27,7 @@ fn test_error_info_format_literal() {
 
 #[test]
 fn test_error_info_format_args() {
-    let info = error_info!("test message: {}", 42);
-    let err = errcode::Error::from_info(info);
+    let err = errcode::Error::from_info(error_info!("test message: {}", 42));
     
     #[cfg(feature = "repr_full")]
     {
@@ -55,8 +56,7 @@ fn test_error_info_code_and_format() {
 
 #[test]
 fn test_error_info_code_and_format_args() {
-    let info = error_info!(TestCode::A, "with message: {}", "val");
-    let err = errcode::Error::from_info(info);
+    let err = errcode::Error::from_info(error_info!(TestCode::A, "with message: {}", "val"));
     assert!(err.is(TestCode::A));
     #[cfg(feature = "repr_full")]
     {
@@ -142,3 +142,24 @@ fn test_ensure_macro_with_code() {
     assert!(err.is(TestCode::B));
     assert!(err.to_string().contains("failed with code"));
 }
+
+const CODE: &ErrorCodeInfo = TestCode::A.const_info();
+static LOCATION: DecodedLocation = DecodedLocation { module: "macros.rs", line: 7, column: 1 };
+static MESSAGE: ErrorInfoImpl = ErrorInfoImpl::new_message("static {message}");
+static CODE_ONLY: ErrorInfoImpl = ErrorInfoImpl::new_code(CODE);
+static FULL: ErrorInfoImpl =
+    ErrorInfoImpl::new(Some(CODE), StaticMessageInfo::NoFormat("full"), Some(&LOCATION));
+
+#[test]
+fn static_error_info() {
+    let error = Error::from_info(new_error_info(&MESSAGE, None));
+    assert!(error.to_string().starts_with("static {message}"));
+    assert!(!error.has_code());
+
+    let error = Error::from_info(new_error_info(&CODE_ONLY, None));
+    assert!(error.is(TestCode::A));
+
+    let error = Error::from_info(new_error_info(&FULL, None));
+    assert!(error.is(TestCode::A));
+    assert!(error.to_string().starts_with("full (TestCode::A)"));
+}
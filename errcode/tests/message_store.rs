@@ -0,0 +1,52 @@
+use errcode::{Error, MessageStore, error_info};
+use std::cell::UnsafeCell;
+use std::fmt::{Arguments, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A bump allocator over a fixed buffer, which refuses messages once it is full.
+struct FixedArena {
+    buffer: UnsafeCell<[u8; 64]>,
+    used: AtomicUsize,
+}
+unsafe impl Sync for FixedArena {}
+impl MessageStore for FixedArena {
+    fn store(&self, args: &Arguments<'_>) -> Option<&'static str> {
+        let mut message = String::new();
+        message.write_fmt(*args).ok()?;
+        let start = self
+            .used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                (used + message.len() <= 64).then_some(used + message.len())
+            })
+            .ok()?;
+        unsafe {
+            let buffer = &mut *self.buffer.get();
+            let slice = &mut buffer[start..start + message.len()];
+            slice.copy_from_slice(message.as_bytes());
+            Some(std::str::from_utf8_unchecked(slice))
+        }
+    }
+}
+
+static ARENA: FixedArena =
+    FixedArena { buffer: UnsafeCell::new([0; 64]), used: AtomicUsize::new(0) };
+
+#[test]
+fn fixed_arena_message_store() {
+    errcode::set_message_store(&ARENA);
+
+    let value = std::hint::black_box(1);
+    let error = Error::from_info(error_info!("stored {}", value));
+    let long = "x".repeat(100);
+    let overflow = Error::from_info(error_info!("overflow {}", long));
+
+    if cfg!(feature = "repr_full") {
+        assert!(error.to_string().starts_with("stored 1"), "Error: {error}");
+        // once the arena is full, messages are stored on the heap instead
+        assert!(overflow.to_string().starts_with("overflow xxx"), "Error: {overflow}");
+        assert_eq!(ARENA.used.load(Ordering::Relaxed), "stored 1".len());
+    } else {
+        // messages are never formatted without `repr_full`
+        assert_eq!(ARENA.used.load(Ordering::Relaxed), 0);
+    }
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "serde")]
+
+use errcode::{Error, ErrorCode, error_info};
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StatusCode {
+    NotFound = 5,
+    Internal = 13,
+}
+
+#[test]
+fn status_details() {
+    let error = Error::from_info(error_info!(StatusCode::NotFound, "no such user"))
+        .with_context(error_info!(StatusCode::Internal, "request failed"));
+    let details = error.to_status_details();
+
+    assert_eq!(details.code, 13);
+    assert_eq!(details.message, "request failed");
+    assert_eq!(details.metadata, [("cause.0".to_string(), "no such user".to_string())]);
+
+    let json = serde_json::to_value(&details).unwrap();
+    assert_eq!(json["code"], 13);
+    assert_eq!(json["message"], "request failed");
+    assert_eq!(json["metadata"][0][0], "cause.0");
+}
+
+#[test]
+fn status_details_without_code() {
+    let details = Error::from_info(error_info!("no code")).to_status_details();
+    assert_eq!(details.code, 0);
+    assert_eq!(details.message, "no code");
+    assert!(details.metadata.is_empty());
+}
@@ -0,0 +1,112 @@
+#![cfg(feature = "testing")]
+
+use errcode::{DecodedLocation, Error, ErrorCode, ExpectedFrame, TraceBuilder, error_info};
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TestCode {
+    A,
+    B,
+}
+
+#[test]
+fn frames_eq() {
+    let error = Error::from_info(error_info!(TestCode::A, "root cause"))
+        .with_context(error_info!(TestCode::B, "while loading"));
+
+    assert!(
+        error.frames_eq(&[
+            ExpectedFrame::new()
+                .message("while loading")
+                .code(TestCode::B),
+            ExpectedFrame::new().message("root cause").code(TestCode::A),
+        ])
+    );
+    assert!(error.frames_eq(&[ExpectedFrame::new(), ExpectedFrame::new()]));
+
+    assert!(!error.frames_eq(&[ExpectedFrame::new().message("while loading")]));
+    assert!(!error.frames_eq(&[
+        ExpectedFrame::new().code(TestCode::A),
+        ExpectedFrame::new().code(TestCode::A),
+    ]));
+}
+
+const fn at(line: u32) -> DecodedLocation {
+    DecodedLocation { module: "src/app.rs", line, column: 5 }
+}
+
+fn built_error() -> Error {
+    TraceBuilder::new()
+        .frame(Some("root cause"), Some(TestCode::A.const_info()), at(10))
+        .frame(Some("while loading"), None, at(20))
+        .frame(None, Some(TestCode::B.const_info()), at(30))
+        .build()
+}
+
+#[test]
+fn trace_builder_frames() {
+    let error = built_error();
+    assert!(error.is(TestCode::B));
+    assert_eq!(error.root_code().unwrap().variant_name, "A");
+    assert_eq!(error.source_location().unwrap().line, 10);
+    #[cfg(feature = "repr_full")]
+    assert!(
+        error.frames_eq(&[
+            ExpectedFrame::new().code(TestCode::B).location(at(30)),
+            ExpectedFrame::new()
+                .message("while loading")
+                .location(at(20)),
+            ExpectedFrame::new()
+                .message("root cause")
+                .code(TestCode::A)
+                .location(at(10)),
+        ])
+    );
+}
+
+#[test]
+fn trace_builder_golden_display() {
+    // a code without a message is shown as its humanized variant name with this feature
+    let code = match cfg!(feature = "humanize_codes") {
+        true => "b (TestCode::B)",
+        false => "TestCode::B",
+    };
+    #[cfg(feature = "repr_full")]
+    let expected = format!(
+        "\
+{code} [at src/app.rs:30:5]
+    caused by: while loading [at src/app.rs:20:5]
+    caused by: root cause (TestCode::A) [at src/app.rs:10:5]"
+    );
+    #[cfg(not(feature = "repr_full"))]
+    let expected = format!(
+        "\
+{code} [at src/app.rs:30:5]
+    caused by: root cause (TestCode::A) [at src/app.rs:10:5]
+    caused by: <some frames have been omitted>"
+    );
+    assert_eq!(built_error().to_string(), expected);
+}
+
+#[test]
+fn trace_builder_golden_debug() {
+    const FRAME_B: &str = "ErrorFrameImpl { data: NormalFrame(None, Some(ErrorCodeInfo { \
+        variant: TestCode::B, value: 1, message: None, deprecated: None, doc_url: None, \
+        group: None, severity: None, metadata: [], .. })), location: \
+        Some(DecodedLocation { module: \"src/app.rs\", line: 30, column: 5 }) }";
+    const FRAME_A: &str = "ErrorFrameImpl { data: NormalFrame(Some(Static(\"root cause\")), \
+        Some(ErrorCodeInfo { variant: TestCode::A, value: 0, message: None, deprecated: None, \
+        doc_url: None, group: None, severity: None, metadata: [], .. })), \
+        location: Some(DecodedLocation { module: \"src/app.rs\", line: 10, column: 5 }) }";
+    #[cfg(feature = "repr_full")]
+    let expected = format!(
+        "[{FRAME_B}, ErrorFrameImpl {{ data: NormalFrame(Some(Static(\"while loading\")), None), \
+         location: Some(DecodedLocation {{ module: \"src/app.rs\", line: 20, column: 5 }}) }}, \
+         {FRAME_A}]"
+    );
+    #[cfg(not(feature = "repr_full"))]
+    let expected = format!(
+        "[{FRAME_B}, {FRAME_A}, ErrorFrameImpl {{ data: InternalContext(FurtherFramesOmitted), \
+         location: None }}]"
+    );
+    assert_eq!(format!("{:?}", built_error()), expected);
+}
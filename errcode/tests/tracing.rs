@@ -0,0 +1,84 @@
+#![cfg(feature = "tracing")]
+
+use errcode::{Error, ErrorCode, error_info};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+#[derive(ErrorCode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TraceCode {
+    Root,
+    Outer,
+}
+
+#[derive(Clone, Default)]
+struct FieldCollector {
+    fields: Arc<Mutex<Vec<(String, String)>>>,
+}
+impl Visit for FieldCollector {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.fields
+            .lock()
+            .unwrap()
+            .push((field.name().to_string(), format!("{value:?}")));
+    }
+}
+impl Subscriber for FieldCollector {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+    fn event(&self, event: &Event<'_>) {
+        assert_eq!(*event.metadata().level(), Level::WARN);
+        event.record(&mut self.clone());
+    }
+    fn enter(&self, _: &Id) {}
+    fn exit(&self, _: &Id) {}
+}
+
+fn capture(error: &Error) -> Vec<(String, String)> {
+    let collector = FieldCollector::default();
+    tracing::subscriber::with_default(collector.clone(), || error.emit_event(Level::WARN));
+    collector.fields.lock().unwrap().clone()
+}
+
+fn field<'a>(fields: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    fields.iter().find(|x| x.0 == name).map(|x| x.1.as_str())
+}
+
+#[test]
+fn emit_event_fields() {
+    let error = Error::from_info(error_info!(TraceCode::Root, "disk full"))
+        .with_context(error_info!(TraceCode::Outer, "while saving"));
+    let fields = capture(&error);
+
+    assert_eq!(field(&fields, "error.code"), Some("TraceCode::Outer"));
+    assert_eq!(field(&fields, "error.root_code"), Some("TraceCode::Root"));
+    assert_eq!(field(&fields, "error.depth"), Some(&*error.frame_count().to_string()));
+    assert!(
+        field(&fields, "error.frames")
+            .unwrap()
+            .contains("disk full")
+    );
+    assert!(
+        field(&fields, "message")
+            .unwrap()
+            .starts_with("while saving")
+    );
+}
+
+#[test]
+fn emit_event_without_code() {
+    let error = Error::from_info(error_info!("no code"));
+    let fields = capture(&error);
+
+    assert_eq!(field(&fields, "error.code"), None);
+    assert_eq!(field(&fields, "error.root_code"), None);
+    assert!(field(&fields, "message").unwrap().starts_with("no code"));
+}
@@ -1,5 +1,5 @@
-use proc_macro2::Ident;
-use venial::{Enum, Error, Fields};
+use proc_macro2::{Delimiter, Ident, Literal, TokenTree};
+use venial::{Attribute, Enum, Error, Fields, TypeExpr};
 
 pub struct EnumInfo {
     pub name: Ident,
@@ -8,38 +8,381 @@ pub struct EnumInfo {
 
 pub struct EnumVariantInfo {
     pub name: Ident,
-    pub repr: u32,
+    pub repr: u64,
+    pub negative: bool,
     pub message: Option<String>,
+    pub deprecated: Option<Literal>,
+    pub aliases: Vec<u64>,
+    pub doc_url: Option<Literal>,
+    pub group: Option<Literal>,
+    /// The name of the `Severity` variant for this code.
+    pub severity: Option<Ident>,
+    /// The `#[code(meta(key = "value"))]` entries for this code, in the order they were given.
+    pub metadata: Vec<(String, Literal)>,
+    /// The type of the payload of a `#[code(transparent)]` variant, which is used as its value.
+    ///
+    /// The `#[code(unknown_variant = ...)]` of an enum is treated as a transparent variant with a
+    /// `u32` payload.
+    pub transparent: Option<TypeExpr>,
+    /// Whether this is the `#[code(unknown_variant = ...)]` of the enum, whose payloads are equal
+    /// to unit variants with the same value.
+    pub unknown: bool,
 }
 
 pub fn parse(item: &Enum) -> Result<EnumInfo, Error> {
     if item
         .generic_params
         .as_ref()
-        .map_or(false, |x| !x.params.is_empty())
+        .is_some_and(|x| !x.params.is_empty())
     {
         return Err(Error::new("#[derive(ErrorCode)] cannot be used on generic enums."));
     }
 
+    let mut doc_url = None;
+    let mut group = None;
+    let mut severity = None;
+    let mut metadata = Vec::new();
+    let mut unknown_variant = None;
+    parse_code_options(&item.attributes, |key, value| {
+        match key.to_string().as_str() {
+            "doc_url" => doc_url = Some(parse_str_option(key, value)?),
+            "group" => group = Some(parse_str_option(key, value)?),
+            "severity" => severity = Some(parse_severity_option(key, value)?),
+            "meta" => parse_meta_option(key, value, &mut metadata)?,
+            "unknown_variant" => unknown_variant = Some(parse_ident_option(key, value)?),
+            _ => return Err(unknown_option(key)),
+        }
+        Ok(())
+    })?;
+
     let mut variants = Vec::new();
-    for (i, (variant, _)) in item.variants.inner.iter().enumerate() {
-        match &variant.fields {
-            Fields::Unit => {}
+    let mut next_discriminant = 0i128;
+    for (variant, _) in item.variants.inner.iter() {
+        let payload = match &variant.fields {
+            Fields::Unit => None,
+            Fields::Tuple(fields) if fields.fields.len() == 1 => {
+                Some(fields.fields.inner[0].0.ty.clone())
+            }
             _ => {
                 return Err(Error::new_at_span(
                     variant.span(),
-                    "#[derive(EnumCode)] does not support variants with fields.",
+                    "#[derive(ErrorCode)] only supports unit variants, or `#[code(transparent)]` \
+                     variants with a single field.",
                 ));
             }
+        };
+
+        let discriminant = match &variant.value {
+            Some(value) => parse_discriminant(&value.value)?,
+            None => next_discriminant,
+        };
+        if discriminant < i64::MIN as i128 || discriminant > u64::MAX as i128 {
+            return Err(Error::new_at_span(
+                variant.span(),
+                "#[derive(ErrorCode)] only supports discriminants that fit in 64 bits.",
+            ));
         }
+        next_discriminant = discriminant + 1;
 
-        variants.push(EnumVariantInfo {
+        let mut info = EnumVariantInfo {
             name: variant.name.clone(),
             // TODO: Make sure repr matches the enum repr for optimization purposes.
-            repr: i as u32,
+            repr: discriminant as u64,
+            negative: discriminant < 0,
             message: None,
-        });
+            deprecated: None,
+            aliases: Vec::new(),
+            doc_url: doc_url.clone(),
+            group: group.clone(),
+            severity: severity.clone(),
+            metadata: metadata.clone(),
+            transparent: None,
+            unknown: false,
+        };
+        let transparent = parse_code_attributes(&variant.attributes, &mut info)?;
+        let is_unknown = unknown_variant.as_ref() == Some(&variant.name);
+        match (transparent, payload) {
+            (false, Some(payload)) if is_unknown => {
+                if payload.tokens.len() != 1 || payload.tokens[0].to_string() != "u32" {
+                    return Err(Error::new_at_span(
+                        variant.span(),
+                        "the `unknown_variant` must have a single `u32` field.",
+                    ));
+                }
+                if !info.aliases.is_empty() {
+                    return Err(Error::new_at_span(
+                        variant.span(),
+                        "the `unknown_variant` cannot have aliases.",
+                    ));
+                }
+                info.transparent = Some(payload);
+                info.unknown = true;
+            }
+            (_, None) if is_unknown => {
+                return Err(Error::new_at_span(
+                    variant.span(),
+                    "the `unknown_variant` must have a single `u32` field.",
+                ));
+            }
+            (true, _) if unknown_variant.is_some() => {
+                return Err(Error::new_at_span(
+                    variant.span(),
+                    "`#[code(transparent)]` cannot be combined with `unknown_variant`.",
+                ));
+            }
+            (false, None) => {}
+            (true, Some(payload)) if info.aliases.is_empty() => info.transparent = Some(payload),
+            (true, Some(_)) => {
+                return Err(Error::new_at_span(
+                    variant.span(),
+                    "`#[code(transparent)]` variants cannot have aliases.",
+                ));
+            }
+            (true, None) => {
+                return Err(Error::new_at_span(
+                    variant.span(),
+                    "`#[code(transparent)]` requires a variant with a single field.",
+                ));
+            }
+            (false, Some(_)) => {
+                return Err(Error::new_at_span(
+                    variant.span(),
+                    "variants with a field must be marked `#[code(transparent)]`.",
+                ));
+            }
+        }
+        variants.push(info);
+    }
+    if let Some(name) = &unknown_variant
+        && !variants.iter().any(|x| x.name == *name)
+    {
+        return Err(Error::new_at_span(
+            name.span(),
+            format!("no variant named `{name}` for `unknown_variant`."),
+        ));
+    }
+    if variants.iter().filter(|x| x.transparent.is_some()).count() > 1 {
+        return Err(Error::new("#[derive(ErrorCode)] supports at most one transparent variant."));
+    }
+
+    let mut values = Vec::new();
+    for variant in variants.iter().filter(|x| x.transparent.is_none()) {
+        for value in core::iter::once(variant.repr).chain(variant.aliases.iter().copied()) {
+            if values.contains(&value) {
+                return Err(Error::new_at_span(
+                    variant.name.span(),
+                    "#[derive(ErrorCode)] requires values and aliases to be unique.",
+                ));
+            }
+            values.push(value);
+        }
     }
 
     Ok(EnumInfo { name: item.name.clone(), variants })
 }
+
+/// Parses the `#[code(...)]` attributes on a variant, returning whether it is marked as
+/// transparent.
+fn parse_code_attributes(attrs: &[Attribute], info: &mut EnumVariantInfo) -> Result<bool, Error> {
+    let mut transparent = false;
+    parse_code_options(attrs, |key, value| {
+        match key.to_string().as_str() {
+            "transparent" if value.is_empty() => transparent = true,
+            "deprecated" => info.deprecated = Some(parse_str_option(key, value)?),
+            "alias" => info.aliases.push(parse_int_option(key, value)?),
+            "doc_url" => info.doc_url = Some(parse_str_option(key, value)?),
+            "group" => info.group = Some(parse_str_option(key, value)?),
+            "severity" => info.severity = Some(parse_severity_option(key, value)?),
+            "meta" => parse_meta_option(key, value, &mut info.metadata)?,
+            _ => return Err(unknown_option(key)),
+        }
+        Ok(())
+    })?;
+    Ok(transparent)
+}
+
+/// Calls a function with the key and value tokens of each option in `#[code(...)]` attributes.
+fn parse_code_options(
+    attrs: &[Attribute],
+    mut option_fn: impl FnMut(&Ident, &[TokenTree]) -> Result<(), Error>,
+) -> Result<(), Error> {
+    for attr in attrs {
+        if attr.get_single_path_segment().is_none_or(|x| x != "code") {
+            continue;
+        }
+
+        let tokens = attr.get_value_tokens();
+        for option in tokens.split(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == ',')) {
+            let (key, value) = match option {
+                [] => continue,
+                [TokenTree::Ident(key), value @ ..] => (key, value),
+                [other, ..] => {
+                    return Err(Error::new_at_span(other.span(), "expected a `#[code]` option."));
+                }
+            };
+            option_fn(key, value)?;
+        }
+    }
+    Ok(())
+}
+
+fn unknown_option(key: &Ident) -> Error {
+    Error::new_at_span(key.span(), format!("unknown `#[code]` option: `{key}`"))
+}
+
+/// Parses the value of a `key = "value"` option.
+fn parse_str_option(key: &Ident, value: &[TokenTree]) -> Result<Literal, Error> {
+    match value {
+        [TokenTree::Punct(eq), TokenTree::Literal(lit)]
+            if eq.as_char() == '=' && is_str_literal(lit) =>
+        {
+            Ok(lit.clone())
+        }
+        _ => Err(Error::new_at_span(
+            key.span(),
+            format!("expected a string literal, e.g. `{key} = \"...\"`."),
+        )),
+    }
+}
+
+/// Parses the value of a `key = Name` option.
+fn parse_ident_option(key: &Ident, value: &[TokenTree]) -> Result<Ident, Error> {
+    match value {
+        [TokenTree::Punct(eq), TokenTree::Ident(ident)] if eq.as_char() == '=' => Ok(ident.clone()),
+        _ => Err(Error::new_at_span(
+            key.span(),
+            format!("expected a variant name, e.g. `{key} = Unknown`."),
+        )),
+    }
+}
+
+/// Parses the value of a `severity = "..."` option into the name of a `Severity` variant.
+fn parse_severity_option(key: &Ident, value: &[TokenTree]) -> Result<Ident, Error> {
+    let lit = parse_str_option(key, value)?;
+    let name = match lit.to_string().as_str() {
+        "\"info\"" => "Info",
+        "\"warning\"" => "Warning",
+        "\"error\"" => "Error",
+        "\"fatal\"" => "Fatal",
+        _ => {
+            return Err(Error::new_at_span(
+                lit.span(),
+                "expected one of `\"info\"`, `\"warning\"`, `\"error\"` or `\"fatal\"`.",
+            ));
+        }
+    };
+    Ok(Ident::new(name, lit.span()))
+}
+
+/// Parses the entries of a `meta(key = "value", ...)` option into `metadata`.
+///
+/// An entry replaces any earlier entry with the same key, so variants can override the metadata
+/// they inherit from the enum.
+fn parse_meta_option(
+    key: &Ident,
+    value: &[TokenTree],
+    metadata: &mut Vec<(String, Literal)>,
+) -> Result<(), Error> {
+    let group = match value {
+        [TokenTree::Group(group)] if group.delimiter() == Delimiter::Parenthesis => group,
+        _ => {
+            return Err(Error::new_at_span(
+                key.span(),
+                format!("expected a list of entries, e.g. `{key}(name = \"...\")`."),
+            ));
+        }
+    };
+    let tokens: Vec<_> = group.stream().into_iter().collect();
+    for entry in tokens.split(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == ',')) {
+        let (name, value) = match entry {
+            [] => continue,
+            [TokenTree::Ident(name), value @ ..] => (name, value),
+            [other, ..] => {
+                return Err(Error::new_at_span(other.span(), "expected a metadata key."));
+            }
+        };
+        let value = parse_str_option(name, value)?;
+        let name = name.to_string();
+        match metadata.iter_mut().find(|(x, _)| *x == name) {
+            Some(existing) => existing.1 = value,
+            None => metadata.push((name, value)),
+        }
+    }
+    Ok(())
+}
+
+/// Parses the value of a `key = 123` option.
+fn parse_int_option(key: &Ident, value: &[TokenTree]) -> Result<u64, Error> {
+    let int = match value {
+        [TokenTree::Punct(eq), value] if eq.as_char() == '=' => parse_discriminant(value)?,
+        [TokenTree::Punct(eq), TokenTree::Punct(neg), TokenTree::Literal(lit)]
+            if eq.as_char() == '=' && neg.as_char() == '-' =>
+        {
+            -parse_int_literal(lit)?
+        }
+        _ => {
+            return Err(Error::new_at_span(
+                key.span(),
+                format!("expected an integer literal, e.g. `{key} = 123`."),
+            ));
+        }
+    };
+    if int < i64::MIN as i128 || int > u64::MAX as i128 {
+        return Err(Error::new_at_span(
+            key.span(),
+            "#[derive(ErrorCode)] only supports values that fit in 64 bits.",
+        ));
+    }
+    Ok(int as u64)
+}
+
+fn is_str_literal(lit: &Literal) -> bool {
+    let str = lit.to_string();
+    str.starts_with('"') || str.starts_with("r\"") || str.starts_with("r#")
+}
+
+/// Parses an explicit enum discriminant.
+///
+/// Only integer literals are supported. Negative values must be parenthesized, e.g. `(-1)`.
+fn parse_discriminant(value: &TokenTree) -> Result<i128, Error> {
+    match value {
+        TokenTree::Literal(lit) => parse_int_literal(lit),
+        TokenTree::Group(group)
+            if matches!(group.delimiter(), Delimiter::Parenthesis | Delimiter::None) =>
+        {
+            let tokens: Vec<_> = group.stream().into_iter().collect();
+            match tokens.as_slice() {
+                [inner] => parse_discriminant(inner),
+                [TokenTree::Punct(punct), TokenTree::Literal(lit)] if punct.as_char() == '-' => {
+                    Ok(-parse_int_literal(lit)?)
+                }
+                _ => Err(unsupported_discriminant(value)),
+            }
+        }
+        _ => Err(unsupported_discriminant(value)),
+    }
+}
+
+fn parse_int_literal(lit: &Literal) -> Result<i128, Error> {
+    let str = lit.to_string().replace('_', "");
+    let (radix, digits) = match str.get(..2) {
+        Some("0x") => (16, &str[2..]),
+        Some("0o") => (8, &str[2..]),
+        Some("0b") => (2, &str[2..]),
+        _ => (10, str.as_str()),
+    };
+    let digits = match digits.find(['i', 'u']) {
+        Some(idx) => &digits[..idx],
+        None => digits,
+    };
+    i128::from_str_radix(digits, radix).map_err(|_| {
+        Error::new_at_span(lit.span(), "#[derive(ErrorCode)] expects an integer discriminant.")
+    })
+}
+
+fn unsupported_discriminant(value: &TokenTree) -> Error {
+    Error::new_at_span(
+        value.span(),
+        "#[derive(ErrorCode)] only supports integer literals as discriminants.",
+    )
+}
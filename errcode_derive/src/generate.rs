@@ -1,5 +1,5 @@
 use crate::enum_info::EnumInfo;
-use proc_macro2::{Ident, Span, TokenStream};
+use proc_macro2::{Ident, Literal, Span, TokenStream};
 use quote::quote;
 
 pub fn generate(info: EnumInfo) -> TokenStream {
@@ -17,7 +17,40 @@ pub fn generate(info: EnumInfo) -> TokenStream {
         .map(|x| Ident::new(&format!("STATIC_INFO_{x}"), Span::call_site()))
         .collect();
 
-    let ids: Vec<_> = info.variants.iter().map(|x| x.repr).collect();
+    // unit variants are mapped to fixed values, while the transparent variant (if any) handles
+    // every other value
+    let unit: Vec<_> = (0..info.variants.len())
+        .filter(|&x| info.variants[x].transparent.is_none())
+        .collect();
+    let transparent = info
+        .variants
+        .iter()
+        .enumerate()
+        .find_map(|(i, x)| x.transparent.as_ref().map(|ty| (i, x, ty)));
+
+    let ids: Vec<_> = info
+        .variants
+        .iter()
+        .map(|x| Literal::u64_suffixed(x.repr))
+        .collect();
+    let aliases: Vec<Vec<_>> = info
+        .variants
+        .iter()
+        .map(|x| {
+            x.aliases
+                .iter()
+                .map(|x| Literal::u64_suffixed(*x))
+                .collect()
+        })
+        .collect();
+    let values: Vec<_> = info
+        .variants
+        .iter()
+        .map(|x| match x.negative {
+            true => Literal::i128_unsuffixed(x.repr as i64 as i128),
+            false => Literal::i128_unsuffixed(x.repr as i128),
+        })
+        .collect();
     let variant_names: Vec<_> = info.variants.iter().map(|x| x.name.to_string()).collect();
     let variant: Vec<_> = info.variants.iter().map(|x| &x.name).collect();
     let message_data: Vec<_> = info
@@ -29,18 +62,172 @@ pub fn generate(info: EnumInfo) -> TokenStream {
         })
         .collect();
 
+    let deprecated: Vec<_> = info
+        .variants
+        .iter()
+        .map(|x| match &x.deprecated {
+            None => quote! { #internal::None },
+            Some(msg) => quote! { #internal::Some(#msg) },
+        })
+        .collect();
+
+    let doc_url: Vec<_> = info
+        .variants
+        .iter()
+        .map(|x| match &x.doc_url {
+            None => quote! { #internal::None },
+            Some(url) => quote! { #internal::Some(#url) },
+        })
+        .collect();
+
+    let group: Vec<_> = info
+        .variants
+        .iter()
+        .map(|x| match &x.group {
+            None => quote! { #internal::None },
+            Some(group) => quote! { #internal::Some(#group) },
+        })
+        .collect();
+
+    let severity: Vec<_> = info
+        .variants
+        .iter()
+        .map(|x| match &x.severity {
+            None => quote! { #internal::None },
+            Some(severity) => quote! { #internal::Some(#internal::Severity::#severity) },
+        })
+        .collect();
+
+    let metadata: Vec<_> = info
+        .variants
+        .iter()
+        .map(|x| {
+            let (keys, values): (Vec<_>, Vec<_>) = x.metadata.iter().cloned().unzip();
+            quote! { &[#((#keys, #values)),*] }
+        })
+        .collect();
+
+    let unit_variant: Vec<_> = unit.iter().map(|&x| variant[x]).collect();
+    let unit_ids: Vec<_> = unit.iter().map(|&x| &ids[x]).collect();
+    let unit_aliases: Vec<_> = unit.iter().map(|&x| &aliases[x]).collect();
+    let unit_error_code_info_ident: Vec<_> =
+        unit.iter().map(|&x| &error_code_info_ident[x]).collect();
+    let unit_static_info_ident: Vec<_> = unit.iter().map(|&x| &static_info_ident[x]).collect();
+
+    let (
+        transparent_info,
+        transparent_const_info,
+        transparent_source,
+        transparent_is_value,
+        transparent_from_value,
+        transparent_payload,
+    ) = match transparent {
+        Some((i, variant, payload_ty)) => {
+            let name = &variant.name;
+            let code_info = &error_code_info_ident[i];
+            let static_info = &static_info_ident[i];
+            let is_payload = !variant.unknown;
+            let is_value = quote! {
+                #core::matches!(self, #ty::#name(payload) if payload as i64 as u64 == value)
+            };
+            // negative payloads are stored in two's complement, so they only fit as an `i64`
+            let from_value = quote! {
+                match <#payload_ty as #core::convert::TryFrom<u64>>::try_from(value) {
+                    #core::result::Result::Ok(payload) => #ty::#name(payload),
+                    #core::result::Result::Err(_) => {
+                        match <#payload_ty as #core::convert::TryFrom<i64>>::try_from(value as i64) {
+                            #core::result::Result::Ok(payload) => #ty::#name(payload),
+                            #core::result::Result::Err(_) => {
+                                #core::panic!("unknown value: {value}")
+                            }
+                        }
+                    }
+                }
+            };
+            (
+                quote! {
+                    #ty::#name(_) => #core::panic!(
+                        "transparent error codes cannot be used in const contexts"
+                    ),
+                },
+                quote! {
+                    match self {
+                        #ty::#name(_) => &#code_info,
+                        _ => ConstHelperType.info(self),
+                    }
+                },
+                quote! {
+                    #[allow(unused_comparisons)]
+                    #ty::#name(payload) => {
+                        static INTERNED: #internal::InternTable = #internal::InternTable::new();
+                        INTERNED.get(&#static_info, payload as i64 as u64, payload < 0, #is_payload)
+                    }
+                },
+                quote! { _ => #is_value, },
+                quote! { _ => #from_value, },
+                match is_payload {
+                    true => quote! {
+                        fn is_payload(self, value: u64) -> bool {
+                            #is_value
+                        }
+                        fn from_payload(value: u64) -> Self {
+                            #from_value
+                        }
+                    },
+                    false => quote! {},
+                },
+            )
+        }
+        None => (
+            quote! {},
+            quote! { ConstHelperType.info(self) },
+            quote! {},
+            quote! { _ => false, },
+            quote! { _ => #core::panic!("unknown value: {value}"), },
+            quote! {},
+        ),
+    };
+
+    // `const_info` is only generated with the feature, as it may clash with methods of the enum
+    let const_info = match cfg!(feature = "const_info") {
+        true => quote! {
+            impl #ty {
+                /// Returns the error code info for this value.
+                ///
+                /// This can be used in const contexts, such as to build static tables of error
+                /// codes. For a `#[code(transparent)]` variant, this is the info of the variant
+                /// itself, whose value is its discriminant rather than the payload.
+                pub const fn const_info(self) -> &'static #internal::ErrorCodeInfo {
+                    #transparent_const_info
+                }
+            }
+        },
+        false => quote! {},
+    };
+
     quote! {
         #[automatically_derived]
+        #[allow(deprecated)]
         const _: () = {
             const TYPE_ID: #core::any::TypeId = #core::any::TypeId::of::<#ty>();
             #(
-                static #error_code_info_ident: #internal::ErrorCodeInfo = #internal::ErrorCodeInfo {
-                    tid: TYPE_ID,
-                    value: #ids,
-                    type_name: #ty_name,
-                    variant_name: #variant_names,
-                    message: #message_data,
+                static #error_code_info_ident: #internal::ErrorCodeInfo = {
+                    let mut info = #internal::ErrorCodeInfo::new(
+                        TYPE_ID,
+                        #ty_name,
+                        #variant_names,
+                        #values,
+                    );
+                    info.message = #message_data;
+                    info.deprecated = #deprecated;
+                    info.doc_url = #doc_url;
+                    info.group = #group;
+                    info.severity = #severity;
+                    info.metadata = #metadata;
+                    info
                 };
+            )*
+            #(
                 static #static_info_ident: #internal::ErrorInfoImpl =
                     #internal::wrap_code(&#error_code_info_ident);
             )*
@@ -49,32 +236,37 @@ pub fn generate(info: EnumInfo) -> TokenStream {
             impl ConstHelperType {
                 pub const fn info(&self, value: #ty) -> &'static #internal::ErrorCodeInfo {
                     match value {
-                        #(#ty::#variant => &#error_code_info_ident,)*
+                        #(#ty::#unit_variant => &#unit_error_code_info_ident,)*
+                        #transparent_info
                     }
                 }
             }
 
+            #const_info
+
             impl #internal::ErrorCodePrivate for #ty {
                 type ConstHelper = ConstHelperType;
                 const CONST_HELPER_INSTANCE: ConstHelperType = ConstHelperType;
 
                 fn error_source(self) -> &'static #internal::ErrorInfoImpl {
                     match self {
-                        #(#ty::#variant => &#static_info_ident,)*
+                        #(#ty::#unit_variant => &#unit_static_info_ident,)*
+                        #transparent_source
                     }
                 }
-                fn is_value(self, value: u32) -> bool {
+                fn is_value(self, value: u64) -> bool {
                     match value {
-                        #(#ids => #core::matches!(self, #ty::#variant),)*
-                        _ => false,
+                        #(#unit_ids #(| #unit_aliases)* => #core::matches!(self, #ty::#unit_variant),)*
+                        #transparent_is_value
                     }
                 }
-                fn from_value(value: u32) -> Self {
+                fn from_value(value: u64) -> Self {
                     match value {
-                        #(#ids => #ty::#variant,)*
-                        _ => #core::panic!("unknown value: {value}"),
+                        #(#unit_ids #(| #unit_aliases)* => #ty::#unit_variant,)*
+                        #transparent_from_value
                     }
                 }
+                #transparent_payload
             }
             impl #errcode::ErrorCode for #ty {}
         };
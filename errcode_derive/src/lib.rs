@@ -8,7 +8,7 @@ extern crate proc_macro;
 use proc_macro2::TokenStream;
 use venial::Error;
 
-#[proc_macro_derive(ErrorCode, attributes(errmsg))]
+#[proc_macro_derive(ErrorCode, attributes(errmsg, code))]
 pub fn derive_error_code(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let result = derive_error_code_0(input.into());
     result.unwrap_or_else(|err| err.to_compile_error()).into()